@@ -66,6 +66,11 @@ fn main() {
 
   println!("cargo:rustc-link-lib=static=snowboy-detect");
 
+  // `cc` emits its own rerun-if directives, which turns off cargo's default
+  // change detection, so the wrapper has to be listed explicitly.
+  println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-changed=rsnowboywrapper");
+
   // build dependency for snowboy-detect
   cc::Build::new()
   .cpp(true)
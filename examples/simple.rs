@@ -1,68 +1,46 @@
-use rsnowboy::SnowboyDetect;
-
-fn main() {
-  let resource_filename = "resources/common.res";
-  let model_filename = "resources/models/snowboy.umdl";
-  let sensitivity_str = "1";
-  let audio_gain = 1 as f32;
-  let apply_frontend = false;
-
-  let detector = SnowboyDetect::new(resource_filename, model_filename);
-
-  detector.set_sensitivity(sensitivity_str);
-  detector.set_audio_gain(audio_gain);
-  detector.apply_frontend(apply_frontend);
-
-  let mut rec_count = 0;
-  let mut sil_count = 0;
-  let mut save_buffer: Vec<i16> = Vec::new();
-
+use rsnowboy::utterance::UtteranceRecorder;
+use rsnowboy::wav::WavWriter;
+use rsnowboy::{DetectionResult, SnowboyDetect, SnowboyError};
+
+fn main() -> Result<(), SnowboyError> {
+  let mut detector = SnowboyDetect::builder()
+    .resource("resources/common.res")
+    .model("resources/models/snowboy.umdl", 1.0)
+    .audio_gain(1.0)
+    .apply_frontend(false)
+    .build()?;
+
+  // Records from 2 seconds before the hotword until 700 ms of silence.
+  let mut recorder = UtteranceRecorder::for_format(detector.format())?
+    .trailing_silence(700)
+    .max_length(10_000);
+
+  println!("Listening...");
   // loop get voice
 //  loop {
-    let mut voice = voice(); // get voice from your device
-    let result = detector.run_short_array_detection(voice.as_ptr(), voice.len() as i32, false);
-
-    match result {
-      0 => {
-        println!("voice");
-        save_buffer.append(voice.as_mut());
-        rec_count += 1;
-        sil_count = 0;
-      }
-      1 => {
-        println!("Hotword {} detected!", result);
-        // play resources/ding.wav
-        save_buffer.clear();
-        rec_count = 1;
-      }
-      -2 => {
-        println!("silence");
-        save_buffer.append(voice.as_mut());
-        sil_count += 1;
+    let voice = voice(); // get voice from your device
+    let result = detector.detect(&voice, false)?;
 
-        if rec_count > 0 {
-          save_buffer.append(voice.as_mut());
-          sil_count += 1;
-          println!("silence");
-        }
+    if let DetectionResult::Hotword { index, .. } = &result {
+      println!("Hotword {} detected!", index);
+      // play resources/ding.wav
+    }
 
-        if sil_count > 2 && rec_count > 1 {
-          /*
-          play resources/dong.wav
-          There you can write `save_buffer` to wave file, and use any speech to text service convert this voice
-          file to text, and do any thing.
-          */
-          rec_count = 0;
-          sil_count = 0;
-          save_buffer.clear();
-          println!("Listening...");
-        }
-      }
-      _ => {}
+    if let Some(utterance) = recorder.push(&voice, &result)? {
+      /*
+      play resources/dong.wav
+      There you can use any speech to text service convert this voice file to text, and do any thing.
+      */
+      println!("Utterance ended by {:?}", utterance.end_reason);
+      let mut writer = WavWriter::create("utterance.wav", detector.format())?;
+      writer.write_samples(&utterance.audio)?;
+      writer.finalize()?;
+      println!("Listening...");
     }
 
 //  }
 
+  Ok(())
 }
 
 fn voice() -> Vec<i16> {
@@ -72,4 +50,3 @@ fn voice() -> Vec<i16> {
     -1, -5, 20, 22, 24, 24, 14, 10, 15, 11
   ]
 }
-
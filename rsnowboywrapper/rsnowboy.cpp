@@ -1,126 +1,199 @@
+#include <algorithm>
+#include <cstring>
+#include <exception>
+#include <string>
+
 #include "rsnowboy.h"
 #include "snowboy-detect.h"
 
+namespace {
+
+void set_error(RSnowboyError* error, int code, const char* message) {
+    if (error == NULL) {
+        return;
+    }
+    error->code = code;
+    std::strncpy(error->message, message, RSNOWBOY_ERROR_MESSAGE_SIZE - 1);
+    error->message[RSNOWBOY_ERROR_MESSAGE_SIZE - 1] = '\0';
+}
+
+// Runs <body> and stores any exception it throws in <error>, so exceptions never
+// cross the C boundary. Returns <fallback> if an exception was thrown.
+//
+// Note that snowboy raises most of its own errors from a destructor, which
+// terminates the process before the exception gets here. The Rust side checks
+// files and arguments before calling in for that reason.
+template <typename T, typename F>
+T guard(RSnowboyError* error, T fallback, F body) {
+    set_error(error, RSNOWBOY_OK, "");
+    try {
+        return body();
+    } catch (const std::exception& e) {
+        set_error(error, RSNOWBOY_EXCEPTION, e.what());
+    } catch (...) {
+        set_error(error, RSNOWBOY_UNKNOWN_EXCEPTION, "unknown exception");
+    }
+    return fallback;
+}
+
+template <typename F>
+void guard_void(RSnowboyError* error, F body) {
+    guard(error, false, [&]() {
+        body();
+        return true;
+    });
+}
+
+snowboy::SnowboyDetect* detect_cast(RSnowboyDetect* detector) {
+    return reinterpret_cast<snowboy::SnowboyDetect*>(detector);
+}
+
+snowboy::SnowboyVad* vad_cast(RSnowboyVad* vad) {
+    return reinterpret_cast<snowboy::SnowboyVad*>(vad);
+}
+
+}  // namespace
+
 
 
 extern "C" {
-RSnowboyDetect* detect_create(const char* resource_filename, const char* model_str) {
-    return reinterpret_cast<RSnowboyDetect*>(new snowboy::SnowboyDetect(std::string(resource_filename),
-                                                                        std::string(model_str)));
+RSnowboyDetect* detect_create(const char* resource_filename, const char* model_str, RSnowboyError* error) {
+    return guard(error, static_cast<RSnowboyDetect*>(NULL), [&]() {
+        return reinterpret_cast<RSnowboyDetect*>(new snowboy::SnowboyDetect(std::string(resource_filename),
+                                                                            std::string(model_str)));
+    });
 }
 
-bool detect_reset(RSnowboyDetect* detector) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->Reset();
+bool detect_reset(RSnowboyDetect* detector, RSnowboyError* error) {
+    return guard(error, false, [&]() { return detect_cast(detector)->Reset(); });
 }
 
-int detect_run_char_detection(RSnowboyDetect* detector, const char* data) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->RunDetection(std::string(data));
+int detect_run_bytes_detection(RSnowboyDetect* detector, const char* data, size_t length, bool is_end,
+                               RSnowboyError* error) {
+    return guard(error, -1, [&]() { return detect_cast(detector)->RunDetection(std::string(data, length), is_end); });
 }
 
 int detect_run_float_array_detection(RSnowboyDetect* detector, const float* const data,
-                                     const int array_length, bool is_end = false) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->RunDetection(data, array_length, is_end);
+                                     const int array_length, bool is_end, RSnowboyError* error) {
+    return guard(error, -1, [&]() { return detect_cast(detector)->RunDetection(data, array_length, is_end); });
 }
 
 int detect_run_short_array_detection(RSnowboyDetect* detector, const int16_t* const data,
-                                     const int array_length, bool is_end = false) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->RunDetection(data, array_length, is_end);
+                                     const int array_length, bool is_end, RSnowboyError* error) {
+    return guard(error, -1, [&]() { return detect_cast(detector)->RunDetection(data, array_length, is_end); });
 }
 
 int detect_run_integer_array_detection(RSnowboyDetect* detector, const int32_t* const data,
-                                       const int array_length, bool is_end = false) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->RunDetection(data, array_length, is_end);
+                                       const int array_length, bool is_end, RSnowboyError* error) {
+    return guard(error, -1, [&]() { return detect_cast(detector)->RunDetection(data, array_length, is_end); });
+}
+
+void detect_set_sensitivity(RSnowboyDetect* detector, const char* sensitivity_str, RSnowboyError* error) {
+    guard_void(error, [&]() { detect_cast(detector)->SetSensitivity(std::string(sensitivity_str)); });
 }
 
-void detect_set_sensitivity(RSnowboyDetect* detector, const char* sensitivity_str) {
-    reinterpret_cast<snowboy::SnowboyDetect*>(detector)->SetSensitivity(std::string(sensitivity_str));
+void detect_set_high_sensitivity(RSnowboyDetect* detector, const char* high_sensitivity_str, RSnowboyError* error) {
+    guard_void(error, [&]() { detect_cast(detector)->SetHighSensitivity(std::string(high_sensitivity_str)); });
 }
 
-const char* detect_get_sensitivity(RSnowboyDetect* detector) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->GetSensitivity().c_str();
+size_t detect_get_sensitivity(RSnowboyDetect* detector, char* buffer, size_t buffer_size, RSnowboyError* error) {
+    return guard(error, static_cast<size_t>(0), [&]() {
+        const std::string sensitivity = detect_cast(detector)->GetSensitivity();
+        if (buffer_size > 0) {
+            const size_t length = std::min(sensitivity.size(), buffer_size - 1);
+            std::memcpy(buffer, sensitivity.data(), length);
+            buffer[length] = '\0';
+        }
+        return sensitivity.size();
+    });
 }
 
-void detect_set_audio_gain(RSnowboyDetect* detector, const float audio_gain) {
-    reinterpret_cast<snowboy::SnowboyDetect*>(detector)->SetAudioGain(audio_gain);
+void detect_set_audio_gain(RSnowboyDetect* detector, const float audio_gain, RSnowboyError* error) {
+    guard_void(error, [&]() { detect_cast(detector)->SetAudioGain(audio_gain); });
 }
 
-void detect_update_model(RSnowboyDetect* detector) {
-    reinterpret_cast<snowboy::SnowboyDetect*>(detector)->UpdateModel();
+void detect_update_model(RSnowboyDetect* detector, RSnowboyError* error) {
+    guard_void(error, [&]() { detect_cast(detector)->UpdateModel(); });
 }
 
-int detect_num_hotwords(RSnowboyDetect* detector) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->NumHotwords();
+int detect_num_hotwords(RSnowboyDetect* detector, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return detect_cast(detector)->NumHotwords(); });
 }
 
-void detect_apply_frontend(RSnowboyDetect* detector, const bool apply_frontend) {
-    reinterpret_cast<snowboy::SnowboyDetect*>(detector)->ApplyFrontend(apply_frontend);
+void detect_apply_frontend(RSnowboyDetect* detector, const bool apply_frontend, RSnowboyError* error) {
+    guard_void(error, [&]() { detect_cast(detector)->ApplyFrontend(apply_frontend); });
 }
 
-int detect_sample_rate(RSnowboyDetect* detector) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->SampleRate();
+int detect_sample_rate(RSnowboyDetect* detector, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return detect_cast(detector)->SampleRate(); });
 }
 
-int detect_num_channels(RSnowboyDetect* detector) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->NumChannels();
+int detect_num_channels(RSnowboyDetect* detector, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return detect_cast(detector)->NumChannels(); });
 }
 
-int detect_bits_per_sample(RSnowboyDetect* detector) {
-    return reinterpret_cast<snowboy::SnowboyDetect*>(detector)->BitsPerSample();
+int detect_bits_per_sample(RSnowboyDetect* detector, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return detect_cast(detector)->BitsPerSample(); });
 }
 
-void detect_destroy(RSnowboyDetect* detector) {
-    delete reinterpret_cast<snowboy::SnowboyDetect*>(detector);
+void detect_destroy(RSnowboyDetect* detector, RSnowboyError* error) {
+    guard_void(error, [&]() { delete detect_cast(detector); });
 }
 
 
 
-RSnowboyVad *vad_create(const char *resource_filename) {
-    return reinterpret_cast<RSnowboyVad*>(new snowboy::SnowboyVad(std::string(resource_filename)));
+RSnowboyVad *vad_create(const char *resource_filename, RSnowboyError* error) {
+    return guard(error, static_cast<RSnowboyVad*>(NULL), [&]() {
+        return reinterpret_cast<RSnowboyVad*>(new snowboy::SnowboyVad(std::string(resource_filename)));
+    });
 }
 
-bool vad_reset(RSnowboyVad *vad){
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->Reset();
+bool vad_reset(RSnowboyVad *vad, RSnowboyError* error) {
+    return guard(error, false, [&]() { return vad_cast(vad)->Reset(); });
 }
 
 
-int vad_run_char(RSnowboyVad *vad, const char* data) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->RunVad(data);
+int vad_run_bytes(RSnowboyVad *vad, const char* data, size_t length, bool is_end, RSnowboyError* error) {
+    return guard(error, -1, [&]() { return vad_cast(vad)->RunVad(std::string(data, length), is_end); });
 }
 
-int vad_run_float_array(RSnowboyVad *vad, const float * const data, const int array_length, bool is_end) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->RunVad(data, array_length, is_end);
+int vad_run_float_array(RSnowboyVad *vad, const float * const data, const int array_length, bool is_end,
+                        RSnowboyError* error) {
+    return guard(error, -1, [&]() { return vad_cast(vad)->RunVad(data, array_length, is_end); });
 }
 
-int vad_run_short_array(RSnowboyVad *vad, const int16_t * const data, const int array_length, bool is_end) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->RunVad(data, array_length, is_end);
+int vad_run_short_array(RSnowboyVad *vad, const int16_t * const data, const int array_length, bool is_end,
+                        RSnowboyError* error) {
+    return guard(error, -1, [&]() { return vad_cast(vad)->RunVad(data, array_length, is_end); });
 }
 
-int vad_run_integer_array(RSnowboyVad *vad, const int32_t * const data, const int array_length, bool is_end) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->RunVad(data, array_length, is_end);
+int vad_run_integer_array(RSnowboyVad *vad, const int32_t * const data, const int array_length, bool is_end,
+                          RSnowboyError* error) {
+    return guard(error, -1, [&]() { return vad_cast(vad)->RunVad(data, array_length, is_end); });
 }
 
-void vad_set_audio_gain(RSnowboyVad *vad, const float audio_gain) {
-    reinterpret_cast<snowboy::SnowboyVad*>(vad)->SetAudioGain(audio_gain);
+void vad_set_audio_gain(RSnowboyVad *vad, const float audio_gain, RSnowboyError* error) {
+    guard_void(error, [&]() { vad_cast(vad)->SetAudioGain(audio_gain); });
 }
 
-void vad_apply_frontend(RSnowboyVad *vad, const bool apply_frontend) {
-    reinterpret_cast<snowboy::SnowboyVad*>(vad)->ApplyFrontend(apply_frontend);
+void vad_apply_frontend(RSnowboyVad *vad, const bool apply_frontend, RSnowboyError* error) {
+    guard_void(error, [&]() { vad_cast(vad)->ApplyFrontend(apply_frontend); });
 }
 
-int vad_sample_rate(RSnowboyVad *vad) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->SampleRate();
+int vad_sample_rate(RSnowboyVad *vad, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return vad_cast(vad)->SampleRate(); });
 }
 
-int vad_num_channels(RSnowboyVad *vad) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->NumChannels();
+int vad_num_channels(RSnowboyVad *vad, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return vad_cast(vad)->NumChannels(); });
 }
 
-int vad_bits_per_sample(RSnowboyVad *vad) {
-    return reinterpret_cast<snowboy::SnowboyVad*>(vad)->BitsPerSample();
+int vad_bits_per_sample(RSnowboyVad *vad, RSnowboyError* error) {
+    return guard(error, 0, [&]() { return vad_cast(vad)->BitsPerSample(); });
 }
 
-void vad_destroy(RSnowboyVad *vad) {
-    delete reinterpret_cast<snowboy::SnowboyVad*>(vad);
+void vad_destroy(RSnowboyVad *vad, RSnowboyError* error) {
+    guard_void(error, [&]() { delete vad_cast(vad); });
 }
 
 
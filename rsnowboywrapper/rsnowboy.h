@@ -2,6 +2,7 @@
 #define RSNOWBOY_H
 
 #include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 
 #ifdef __cplusplus
@@ -11,69 +12,90 @@ extern "C" {
 typedef struct RSnowboyDetect RSnowboyDetect;
 typedef struct RSnowboyVad RSnowboyVad;
 
-RSnowboyDetect* detect_create(const char* resource_filename, const char* model_str);
+#define RSNOWBOY_OK 0
+#define RSNOWBOY_EXCEPTION 1
+#define RSNOWBOY_UNKNOWN_EXCEPTION 2
 
-bool detect_reset(RSnowboyDetect* detector);
+#define RSNOWBOY_ERROR_MESSAGE_SIZE 512
 
-int detect_run_char_detection(RSnowboyDetect* detector, const char* data);
+// Every function takes an error as its last argument. <code> is set to
+// RSNOWBOY_OK on success, otherwise <message> holds a NUL-terminated
+// description of the exception thrown by snowboy, and the return value of the
+// function must be ignored.
+typedef struct RSnowboyError {
+  int code;
+  char message[RSNOWBOY_ERROR_MESSAGE_SIZE];
+} RSnowboyError;
+
+RSnowboyDetect* detect_create(const char* resource_filename, const char* model_str, RSnowboyError* error);
+
+bool detect_reset(RSnowboyDetect* detector, RSnowboyError* error);
+
+int detect_run_bytes_detection(RSnowboyDetect* detector, const char* data, size_t length, bool is_end,
+                               RSnowboyError* error);
 
 int detect_run_float_array_detection(RSnowboyDetect* detector, const float* const data,
-                                     const int array_length, bool is_end);
+                                     const int array_length, bool is_end, RSnowboyError* error);
 
 int detect_run_short_array_detection(RSnowboyDetect* detector, const int16_t* const data,
-                                     const int array_length, bool is_end);
+                                     const int array_length, bool is_end, RSnowboyError* error);
 
 int detect_run_integer_array_detection(RSnowboyDetect* detector, const int32_t* const data,
-                                       const int array_length, bool is_end);
+                                       const int array_length, bool is_end, RSnowboyError* error);
+
+void detect_set_sensitivity(RSnowboyDetect* detector, const char* sensitivity_str, RSnowboyError* error);
 
-void detect_set_sensitivity(RSnowboyDetect* detector, const char* sensitivity_str);
+void detect_set_high_sensitivity(RSnowboyDetect* detector, const char* high_sensitivity_str, RSnowboyError* error);
 
-const char* detect_get_sensitivity(RSnowboyDetect* detector);
+// Copies the sensitivity string into <buffer>, truncated to <buffer_size> - 1
+// characters and NUL-terminated. Returns the length of the whole string, so a
+// larger buffer can be passed again if it did not fit.
+size_t detect_get_sensitivity(RSnowboyDetect* detector, char* buffer, size_t buffer_size, RSnowboyError* error);
 
-void detect_set_audio_gain(RSnowboyDetect* detector, const float audio_gain);
+void detect_set_audio_gain(RSnowboyDetect* detector, const float audio_gain, RSnowboyError* error);
 
-void detect_update_model(RSnowboyDetect* detector);
+void detect_update_model(RSnowboyDetect* detector, RSnowboyError* error);
 
-int detect_num_hotwords(RSnowboyDetect* detector);
+int detect_num_hotwords(RSnowboyDetect* detector, RSnowboyError* error);
 
-void detect_apply_frontend(RSnowboyDetect* detector, const bool apply_frontend);
+void detect_apply_frontend(RSnowboyDetect* detector, const bool apply_frontend, RSnowboyError* error);
 
-int detect_sample_rate(RSnowboyDetect* detector);
+int detect_sample_rate(RSnowboyDetect* detector, RSnowboyError* error);
 
-int detect_num_channels(RSnowboyDetect* detector);
+int detect_num_channels(RSnowboyDetect* detector, RSnowboyError* error);
 
-int detect_bits_per_sample(RSnowboyDetect* detector);
+int detect_bits_per_sample(RSnowboyDetect* detector, RSnowboyError* error);
 
-void detect_destroy(RSnowboyDetect* detector);
+void detect_destroy(RSnowboyDetect* detector, RSnowboyError* error);
 
 
 
-RSnowboyVad* vad_create(const char* resource_filename);
+RSnowboyVad* vad_create(const char* resource_filename, RSnowboyError* error);
 
-bool vad_reset(RSnowboyVad *vad);
+bool vad_reset(RSnowboyVad *vad, RSnowboyError* error);
 
-int vad_run_char(RSnowboyVad* vad, const char* data);
+int vad_run_bytes(RSnowboyVad* vad, const char* data, size_t length, bool is_end, RSnowboyError* error);
 
 int vad_run_float_array(RSnowboyVad* vad, const float* const data,
-                        const int array_length, bool is_end);
+                        const int array_length, bool is_end, RSnowboyError* error);
 
 int vad_run_short_array(RSnowboyVad* vad, const int16_t* const data,
-                        const int array_length, bool is_end);
+                        const int array_length, bool is_end, RSnowboyError* error);
 
 int vad_run_integer_array(RSnowboyVad* vad, const int32_t* const data,
-                          const int array_length, bool is_end);
+                          const int array_length, bool is_end, RSnowboyError* error);
 
-void vad_set_audio_gain(RSnowboyVad* vad, const float audio_gain);
+void vad_set_audio_gain(RSnowboyVad* vad, const float audio_gain, RSnowboyError* error);
 
-void vad_apply_frontend(RSnowboyVad* vad, const bool apply_frontend);
+void vad_apply_frontend(RSnowboyVad* vad, const bool apply_frontend, RSnowboyError* error);
 
-int vad_sample_rate(RSnowboyVad* vad);
+int vad_sample_rate(RSnowboyVad* vad, RSnowboyError* error);
 
-int vad_num_channels(RSnowboyVad* vad);
+int vad_num_channels(RSnowboyVad* vad, RSnowboyError* error);
 
-int vad_bits_per_sample(RSnowboyVad* vad);
+int vad_bits_per_sample(RSnowboyVad* vad, RSnowboyError* error);
 
-void vad_destroy(RSnowboyVad* vad);
+void vad_destroy(RSnowboyVad* vad, RSnowboyError* error);
 
 #ifdef __cplusplus
 }
@@ -0,0 +1,130 @@
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnowboyError;
+use crate::sensitivity::Sensitivities;
+use crate::snowboy::SnowboyDetect;
+
+/// Builds a `SnowboyDetect` with its resource, models and settings in one go.
+///
+/// The settings are checked before the models are loaded where possible, and
+/// against the loaded models otherwise, e.g. the number of high
+/// sensitivities, so `build` either returns a detector that is ready to use or
+/// the first setting that does not fit.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use rsnowboy::DetectorBuilder;
+///
+/// let mut detector = DetectorBuilder::new()
+///   .resource("resources/common.res")
+///   .model("resources/models/snowboy.umdl", 0.5)
+///   .audio_gain(1.0)
+///   .apply_frontend(false)
+///   .build()?;
+/// # Ok::<(), rsnowboy::SnowboyError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DetectorBuilder {
+  resource: Option<PathBuf>,
+  models: Vec<PathBuf>,
+  sensitivities: Vec<f32>,
+  high_sensitivity: Option<Vec<f32>>,
+  audio_gain: Option<f32>,
+  apply_frontend: Option<bool>,
+}
+
+impl DetectorBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the resource file, e.g. "resources/common.res".
+  pub fn resource<P>(mut self, resource_filename: P) -> Self where P: AsRef<Path> {
+    self.resource = Some(resource_filename.as_ref().to_path_buf());
+    self
+  }
+
+  /// Adds a hotword model with the sensitivity of its hotwords. A personal
+  /// model holds a single hotword, an universal model may hold several, and
+  /// every hotword of the model gets `sensitivity`.
+  ///
+  /// Hotwords are numbered from 1, as in `DetectionResult::Hotword`, in the
+  /// order the models are added and in the order of each model, see
+  /// `SnowboyDetect::new`. Use `SnowboyDetect::set_hotword_sensitivity` on the
+  /// built detector to tune the hotwords of a model one by one.
+  pub fn model<P>(mut self, model_filename: P, sensitivity: f32) -> Self where P: AsRef<Path> {
+    self.models.push(model_filename.as_ref().to_path_buf());
+    self.sensitivities.push(sensitivity);
+    self
+  }
+
+  /// Sets the high sensitivities, one per hotword rather than per model, see
+  /// `SnowboyDetect::set_high_sensitivity`.
+  pub fn high_sensitivity<V>(mut self, high_sensitivities: V) -> Self where V: Into<Vec<f32>> {
+    self.high_sensitivity = Some(high_sensitivities.into());
+    self
+  }
+
+  /// Sets the gain applied to the input audio, see
+  /// `SnowboyDetect::set_audio_gain`.
+  pub fn audio_gain(mut self, audio_gain: f32) -> Self {
+    self.audio_gain = Some(audio_gain);
+    self
+  }
+
+  /// Turns the frontend processing on or off, see
+  /// `SnowboyDetect::apply_frontend`.
+  pub fn apply_frontend(mut self, apply_frontend: bool) -> Self {
+    self.apply_frontend = Some(apply_frontend);
+    self
+  }
+
+  /// Loads the resource and models, and applies the settings.
+  pub fn build(&self) -> Result<SnowboyDetect, SnowboyError> {
+    let resource = self.resource.as_ref()
+      .ok_or_else(|| SnowboyError::InvalidArgument("no resource file was set".to_string()))?;
+    if self.models.is_empty() {
+      return Err(SnowboyError::InvalidArgument("no hotword model was added".to_string()));
+    }
+    let sensitivities = Sensitivities::new(self.sensitivities.as_slice())?;
+    let high_sensitivity = match &self.high_sensitivity {
+      Some(values) => Some(Sensitivities::new(values.as_slice())?),
+      None => None,
+    };
+    if let Some(audio_gain) = self.audio_gain {
+      if !audio_gain.is_finite() || audio_gain <= 0.0 {
+        return Err(SnowboyError::InvalidArgument(format!("audio gain {} is not a positive number", audio_gain)));
+      }
+    }
+
+    let mut detector = SnowboyDetect::new(resource, &self.models)?;
+    let sensitivities = self.hotword_sensitivities(resource, sensitivities, detector.num_hotwords() as usize)?;
+    detector.set_sensitivity(sensitivities)?;
+    if let Some(high_sensitivity) = high_sensitivity {
+      detector.set_high_sensitivity(high_sensitivity)?;
+    }
+    if let Some(audio_gain) = self.audio_gain {
+      detector.set_audio_gain(audio_gain)?;
+    }
+    if let Some(apply_frontend) = self.apply_frontend {
+      detector.apply_frontend(apply_frontend)?;
+    }
+    Ok(detector)
+  }
+  /// Repeats the sensitivity of each model for every hotword it holds.
+  fn hotword_sensitivities(&self, resource: &Path, sensitivities: Sensitivities,
+                           num_hotwords: usize) -> Result<Sensitivities, SnowboyError> {
+    if num_hotwords == self.models.len() {
+      return Ok(sensitivities);
+    }
+    // snowboy only tells the total, so count the hotwords of each model alone.
+    let mut values = Vec::with_capacity(num_hotwords);
+    for (model, &sensitivity) in self.models.iter().zip(sensitivities.as_slice()) {
+      let count = SnowboyDetect::new(resource, &[model])?.num_hotwords() as usize;
+      values.extend(iter::repeat_n(sensitivity, count));
+    }
+    Sensitivities::new(values)
+  }
+}
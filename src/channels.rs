@@ -0,0 +1,205 @@
+//! Channel adaptation.
+//!
+//! snowboy takes interleaved samples with exactly the number of channels
+//! returned by `SnowboyDetect::num_channels`, one for the bundled resources.
+//! `ChannelAdapter` mixes the channels of a capture device, e.g. a microphone
+//! array, down to that number, from interleaved or planar buffers.
+//!
+//! # Examples
+//!
+//! ```
+//! use rsnowboy::channels::{ChannelAdapter, Layout, Mix};
+//! use rsnowboy::{AudioFormat, SampleFormat};
+//!
+//! let format = AudioFormat::new(16000, 1, 16, SampleFormat::Int);
+//! let adapter = ChannelAdapter::for_format(4, format, Mix::Select(2))?;
+//! let mut samples = Vec::new();
+//! adapter.process_i16(&[1, 2, 3, 4, 5, 6, 7, 8], &mut samples)?;
+//! assert_eq!(samples, vec![3, 7]);
+//!
+//! let planar = ChannelAdapter::for_format(2, format, Mix::Average)?.layout(Layout::Planar);
+//! let mut samples = Vec::new();
+//! planar.process_i16(&[100, 200, 300, 500], &mut samples)?;
+//! assert_eq!(samples, vec![200, 350]);
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::iter;
+
+use crate::error::SnowboyError;
+use crate::format::AudioFormat;
+use crate::resample;
+
+/// How the input channels are mixed into each output channel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mix {
+  /// The mean of all the input channels.
+  Average,
+  /// Only the input channel at this index, starting at 0.
+  Select(usize),
+  /// The sum of the input channels, each multiplied by its weight.
+  Weighted(Vec<f32>),
+}
+
+/// How the samples of the channels are arranged in a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+  /// One sample for each channel in turn, frame after frame.
+  Interleaved,
+  /// Every sample of the first channel, then every sample of the second one,
+  /// and so on.
+  Planar,
+}
+
+/// Converts audio from any number of channels to the number a detector
+/// expects.
+///
+/// Every output channel gets the same mix of the input channels. Unlike the
+/// resampler, the adapter keeps no state, so chunks can be of any number of
+/// frames.
+#[derive(Debug, Clone)]
+pub struct ChannelAdapter {
+  input_channels: usize,
+  output_channels: usize,
+  layout: Layout,
+  /// One weight for each input channel.
+  weights: Vec<f32>,
+}
+
+impl ChannelAdapter {
+  /// Creates an adapter from `input_channels` interleaved channels to
+  /// `output_channels` channels.
+  pub fn new(input_channels: usize, output_channels: usize, mix: Mix) -> Result<Self, SnowboyError> {
+    if input_channels == 0 || output_channels == 0 {
+      return Err(SnowboyError::InvalidArgument(format!("can not mix {} channels into {} channels",
+                                                       input_channels, output_channels)));
+    }
+    let weights = match mix {
+      Mix::Average => vec![1.0 / input_channels as f32; input_channels],
+      Mix::Select(channel) => {
+        if channel >= input_channels {
+          return Err(SnowboyError::InvalidArgument(format!("can not select channel {} of {} channels",
+                                                           channel, input_channels)));
+        }
+        let mut weights = vec![0.0; input_channels];
+        weights[channel] = 1.0;
+        weights
+      }
+      Mix::Weighted(weights) => {
+        if weights.len() != input_channels {
+          return Err(SnowboyError::InvalidArgument(format!("{} weights given for {} channels",
+                                                           weights.len(), input_channels)));
+        }
+        if let Some(weight) = weights.iter().find(|weight| !weight.is_finite()) {
+          return Err(SnowboyError::InvalidArgument(format!("the weight {} is not finite", weight)));
+        }
+        weights
+      }
+    };
+    Ok(Self { input_channels, output_channels, layout: Layout::Interleaved, weights })
+  }
+
+  /// Creates an adapter from `input_channels` channels to the number of
+  /// channels of `format`, e.g. `SnowboyDetect::format`.
+  pub fn for_format(input_channels: usize, format: AudioFormat, mix: Mix) -> Result<Self, SnowboyError> {
+    Self::new(input_channels, format.num_channels as usize, mix)
+  }
+
+  /// Sets the layout of the input buffers, interleaved by default. The output
+  /// is always interleaved.
+  pub fn layout(mut self, layout: Layout) -> Self {
+    self.layout = layout;
+    self
+  }
+
+  pub fn input_channels(&self) -> usize {
+    self.input_channels
+  }
+
+  pub fn output_channels(&self) -> usize {
+    self.output_channels
+  }
+
+  /// Mixes `input`, which must be whole frames, and appends the interleaved
+  /// frames to `output`.
+  pub fn process(&self, input: &[f32], output: &mut Vec<f32>) -> Result<(), SnowboyError> {
+    self.check_frames(input.len())?;
+    let frames = input.len() / self.input_channels;
+    output.reserve(frames * self.output_channels);
+    for frame in 0..frames {
+      let mixed = match self.layout {
+        Layout::Interleaved => self.mix(|channel| input[frame * self.input_channels + channel]),
+        Layout::Planar => self.mix(|channel| input[channel * frames + frame]),
+      };
+      output.extend(iter::repeat_n(mixed, self.output_channels));
+    }
+    Ok(())
+  }
+
+  /// Same as `process`, for 16-bits samples. The output is rounded and
+  /// clipped.
+  pub fn process_i16(&self, input: &[i16], output: &mut Vec<i16>) -> Result<(), SnowboyError> {
+    let input = input.iter().map(|&sample| f32::from(sample)).collect::<Vec<f32>>();
+    let mut mixed = Vec::new();
+    self.process(&input, &mut mixed)?;
+    output.extend(mixed.into_iter().map(resample::to_i16));
+    Ok(())
+  }
+
+  /// Same as `process`, for planar audio given as one buffer for each
+  /// channel, whatever the layout of the adapter. Every buffer must have the
+  /// same length.
+  pub fn process_channels(&self, channels: &[&[f32]], output: &mut Vec<f32>) -> Result<(), SnowboyError> {
+    if channels.len() != self.input_channels {
+      return Err(SnowboyError::InvalidArgument(format!("{} buffers given for {} channels",
+                                                       channels.len(), self.input_channels)));
+    }
+    let frames = channels[0].len();
+    if channels.iter().any(|channel| channel.len() != frames) {
+      return Err(SnowboyError::InvalidArgument("the channel buffers do not have the same length".to_string()));
+    }
+    output.extend((0..frames).flat_map(|frame| {
+      iter::repeat_n(self.mix(|channel| channels[channel][frame]), self.output_channels)
+    }));
+    Ok(())
+  }
+
+  fn mix<F: Fn(usize) -> f32>(&self, sample: F) -> f32 {
+    self.weights.iter().enumerate()
+      .filter(|(_, &weight)| weight != 0.0)
+      .map(|(channel, weight)| sample(channel) * weight)
+      .sum()
+  }
+
+  fn check_frames(&self, samples: usize) -> Result<(), SnowboyError> {
+    if samples.is_multiple_of(self.input_channels) {
+      Ok(())
+    } else {
+      Err(SnowboyError::PartialFrame { samples, channels: self.input_channels })
+    }
+  }
+}
+
+/// Converts planar audio, given as one buffer for each channel, to
+/// interleaved samples appended to `output`. Every buffer must have the same
+/// length.
+pub fn interleave<T: Copy>(channels: &[&[T]], output: &mut Vec<T>) -> Result<(), SnowboyError> {
+  let frames = channels.first().map_or(0, |channel| channel.len());
+  if channels.iter().any(|channel| channel.len() != frames) {
+    return Err(SnowboyError::InvalidArgument("the channel buffers do not have the same length".to_string()));
+  }
+  output.reserve(frames * channels.len());
+  for frame in 0..frames {
+    output.extend(channels.iter().map(|channel| channel[frame]));
+  }
+  Ok(())
+}
+
+/// Converts interleaved samples of `channels` channels to one buffer for each
+/// channel.
+pub fn deinterleave<T: Copy>(samples: &[T], channels: usize) -> Result<Vec<Vec<T>>, SnowboyError> {
+  if channels == 0 || !samples.len().is_multiple_of(channels) {
+    return Err(SnowboyError::PartialFrame { samples: samples.len(), channels });
+  }
+  Ok((0..channels).map(|channel| samples.iter().skip(channel).step_by(channels).copied().collect()).collect())
+}
@@ -0,0 +1,196 @@
+//! Fixed duration chunks.
+//!
+//! snowboy works best with small chunks of audio of a steady size, e.g. 0.1
+//! second: larger ones add latency, smaller ones add CPU usage. Capture APIs
+//! and sockets return whatever they have. `Chunker` buffers those reads and
+//! runs detection on chunks of a set duration.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io::Read;
+//!
+//! use rsnowboy::chunker::Chunker;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let mut chunker = Chunker::for_format(100, detector.format())?;
+//! let mut stream = std::net::TcpStream::connect("127.0.0.1:9000")?;
+//! let mut buffer = [0u8; 1234];
+//! loop {
+//!   let read = stream.read(&mut buffer)?;
+//!   if read == 0 {
+//!     break;
+//!   }
+//!   for detection in chunker.push(&buffer[..read], |chunk, is_end| detector.detect_bytes(chunk, is_end))? {
+//!     if let Some(index) = detection.result.hotword() {
+//!       println!("Hotword {} detected at sample {}", index, detection.sample_offset);
+//!     }
+//!   }
+//! }
+//! chunker.finish(|chunk, is_end| detector.detect_bytes(chunk, is_end))?;
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+use crate::error::SnowboyError;
+use crate::format::AudioFormat;
+use crate::wav::Detection;
+
+/// Error of a `Chunker::push` whose `detect` failed on a chunk.
+///
+/// The chunker is left at the start of the chunk that failed, as if only the
+/// first `consumed` values of the input had been pushed.
+#[derive(Debug)]
+pub struct PushError<T> {
+  /// Results of the chunks before the one that failed.
+  pub detections: Vec<Detection<T>>,
+  /// Number of values of the input that were pushed.
+  pub consumed: usize,
+  pub error: SnowboyError,
+}
+
+impl<T> fmt::Display for PushError<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    self.error.fmt(f)
+  }
+}
+
+impl<T: fmt::Debug> Error for PushError<T> {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    self.error.source()
+  }
+}
+
+impl<T> From<PushError<T>> for SnowboyError {
+  fn from(error: PushError<T>) -> Self {
+    error.error
+  }
+}
+
+/// Splits a stream of interleaved samples into chunks of the same duration.
+///
+/// The chunker works on any type of sample, including the bytes taken by
+/// `SnowboyDetect::detect_bytes`, for which a sample is one byte.
+#[derive(Debug, Clone)]
+pub struct Chunker<S> {
+  /// Number of values in one frame, e.g. 2 for 16-bits mono bytes.
+  frame_len: usize,
+  chunk_frames: usize,
+  buffer: Vec<S>,
+  /// Offset of the first frame of `buffer`, in frames.
+  sample_offset: u64,
+}
+
+impl<S: Copy> Chunker<S> {
+  /// Creates a chunker for chunks of `millis` milliseconds of `frame_len`
+  /// values per frame at `sample_rate` frames per second. `frame_len` is the
+  /// number of channels for samples, and the size of a frame for bytes.
+  pub fn new(millis: u32, sample_rate: u32, frame_len: usize) -> Result<Self, SnowboyError> {
+    let chunk_frames = (u64::from(sample_rate) * u64::from(millis) / 1000) as usize;
+    if chunk_frames == 0 || frame_len == 0 {
+      return Err(SnowboyError::InvalidArgument(format!("can not split {} Hz audio into chunks of {} ms",
+                                                       sample_rate, millis)));
+    }
+    Ok(Self { frame_len, chunk_frames, buffer: Vec::new(), sample_offset: 0 })
+  }
+
+  /// Returns the number of frames in a chunk.
+  pub fn chunk_frames(&self) -> usize {
+    self.chunk_frames
+  }
+
+  /// Returns the offset of the next chunk, in samples per channel from the
+  /// start of the stream.
+  pub fn sample_offset(&self) -> u64 {
+    self.sample_offset
+  }
+
+  /// Returns the number of values that are waiting for a chunk to be full.
+  pub fn buffered(&self) -> usize {
+    self.buffer.len()
+  }
+
+  /// Appends `samples`, of any length, and runs `detect` over every chunk that
+  /// is full, with `is_end` false. Returns the result of each chunk with its
+  /// offset.
+  ///
+  /// When `detect` fails, the results of the chunks before are returned with
+  /// the error, and the chunk that failed and the rest of `samples` are not
+  /// pushed: they can be pushed again from `PushError::consumed`.
+  pub fn push<T, F>(&mut self, samples: &[S], mut detect: F) -> Result<Vec<Detection<T>>, PushError<T>>
+    where F: FnMut(&[S], bool) -> Result<T, SnowboyError> {
+    let chunk_len = self.chunk_frames * self.frame_len;
+    let mut detections = Vec::new();
+    let mut consumed = 0;
+    // Complete the buffered chunk first, then take chunks from `samples`
+    // directly, and only buffer what is left.
+    if !self.buffer.is_empty() {
+      let buffered = self.buffer.len();
+      consumed = (chunk_len - buffered).min(samples.len());
+      self.buffer.extend_from_slice(&samples[..consumed]);
+      if self.buffer.len() < chunk_len {
+        return Ok(detections);
+      }
+      match detect(&self.buffer, false) {
+        Ok(result) => detections.push(self.detection(result)),
+        Err(error) => {
+          self.buffer.truncate(buffered);
+          return Err(PushError { detections, consumed: 0, error });
+        }
+      }
+      self.buffer.clear();
+    }
+    let mut chunks = samples[consumed..].chunks_exact(chunk_len);
+    for chunk in &mut chunks {
+      match detect(chunk, false) {
+        Ok(result) => detections.push(self.detection(result)),
+        Err(error) => return Err(PushError { detections, consumed, error }),
+      }
+      consumed += chunk_len;
+    }
+    self.buffer.extend_from_slice(chunks.remainder());
+    Ok(detections)
+  }
+
+  /// Runs `detect` over the samples that are left with `is_end` true, and
+  /// starts a new stream. The samples must be whole frames.
+  ///
+  /// When the stream ends on a chunk boundary, `detect` is called with an
+  /// empty slice: snowboy is always told that the stream ended, and may still
+  /// report a hotword then. This is how every stream of the crate ends.
+  pub fn finish<T, F>(&mut self, detect: F) -> Result<Detection<T>, SnowboyError>
+    where F: FnOnce(&[S], bool) -> Result<T, SnowboyError> {
+    if !self.buffer.len().is_multiple_of(self.frame_len) {
+      let samples = self.buffer.len();
+      self.reset();
+      return Err(SnowboyError::PartialFrame { samples, channels: self.frame_len });
+    }
+    let detection = detect(&self.buffer, true).map(|result| Detection { result, sample_offset: self.sample_offset });
+    self.reset();
+    detection
+  }
+
+  /// Drops the buffered samples, and starts a new stream at offset 0.
+  pub fn reset(&mut self) {
+    self.buffer.clear();
+    self.sample_offset = 0;
+  }
+
+  /// Pairs the result of a full chunk with its offset, and moves past it.
+  fn detection<T>(&mut self, result: T) -> Detection<T> {
+    let detection = Detection { result, sample_offset: self.sample_offset };
+    self.sample_offset += self.chunk_frames as u64;
+    detection
+  }
+}
+
+impl Chunker<u8> {
+  /// Creates a chunker for bytes of audio in `format`, e.g.
+  /// `SnowboyDetect::format`, for `detect_bytes`.
+  pub fn for_format(millis: u32, format: AudioFormat) -> Result<Self, SnowboyError> {
+    Self::new(millis, format.sample_rate, format.bytes_per_frame())
+  }
+}
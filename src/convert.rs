@@ -0,0 +1,307 @@
+//! Sample format conversion.
+//!
+//! snowboy takes samples in a single format, `SnowboyDetect::format`, 16-bits
+//! integers for the bundled resources. An `AudioBuffer` tags samples with
+//! their encoding, e.g. packed 24-bits or big endian bytes from the network,
+//! and `Converter` turns them into that format. `SnowboyDetect::detect_buffer`
+//! and `SnowboyVad::detect_buffer` do this for every chunk.
+//!
+//! Only the encoding of the samples changes: the sample rate and the number of
+//! channels are left to `resample` and `channels`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rsnowboy::convert::{AudioBuffer, Clipping, Converter, Encoding, Endian};
+//! use rsnowboy::{AudioFormat, SampleFormat};
+//!
+//! let mut converter = Converter::new(AudioFormat::new(16000, 1, 16, SampleFormat::Int));
+//! let mut data = Vec::new();
+//! // 0.5 and -0.25, as big endian 24-bits integers.
+//! let packed = [0x40, 0x00, 0x00, 0xe0, 0x00, 0x00];
+//! converter.convert(AudioBuffer::Bytes { encoding: Encoding::I24, endian: Endian::Big, data: &packed }, &mut data)?;
+//! assert_eq!(data, vec![0x00, 0x40, 0x00, 0xe0]);
+//!
+//! let mut strict = Converter::new(AudioFormat::new(16000, 1, 16, SampleFormat::Int)).clipping(Clipping::Error);
+//! assert!(strict.convert(AudioBuffer::F64(&[1.5]), &mut data).is_err());
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use crate::error::SnowboyError;
+use crate::format::{AudioFormat, SampleFormat};
+
+/// How the samples of a buffer are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+  /// Unsigned 8-bits integers, with silence at 128.
+  U8,
+  I16,
+  /// Signed 24-bits integers, packed in 3 bytes.
+  I24,
+  I32,
+  /// Floating point, between -1 and 1.
+  F32,
+  F64,
+}
+
+impl Encoding {
+  pub fn bytes_per_sample(self) -> usize {
+    match self {
+      Encoding::U8 => 1,
+      Encoding::I16 => 2,
+      Encoding::I24 => 3,
+      Encoding::I32 | Encoding::F32 => 4,
+      Encoding::F64 => 8,
+    }
+  }
+
+  /// Returns the number of significant bits of a sample.
+  fn precision(self) -> u32 {
+    match self {
+      Encoding::U8 => 8,
+      Encoding::I16 => 16,
+      Encoding::I24 | Encoding::F32 => 24,
+      Encoding::I32 => 32,
+      Encoding::F64 => 53,
+    }
+  }
+}
+
+/// Byte order of the samples of `AudioBuffer::Bytes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endian {
+  Little,
+  Big,
+}
+
+impl Endian {
+  /// Returns the byte order of the current platform.
+  pub fn native() -> Self {
+    if cfg!(target_endian = "big") { Endian::Big } else { Endian::Little }
+  }
+}
+
+/// Interleaved samples, tagged with their encoding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioBuffer<'a> {
+  U8(&'a [u8]),
+  I16(&'a [i16]),
+  I32(&'a [i32]),
+  F32(&'a [f32]),
+  F64(&'a [f64]),
+  /// Samples as they were read, e.g. from a file or a socket. `data` must be
+  /// a whole number of samples.
+  Bytes { encoding: Encoding, endian: Endian, data: &'a [u8] },
+}
+
+impl<'a> AudioBuffer<'a> {
+  pub fn encoding(&self) -> Encoding {
+    match self {
+      AudioBuffer::U8(_) => Encoding::U8,
+      AudioBuffer::I16(_) => Encoding::I16,
+      AudioBuffer::I32(_) => Encoding::I32,
+      AudioBuffer::F32(_) => Encoding::F32,
+      AudioBuffer::F64(_) => Encoding::F64,
+      AudioBuffer::Bytes { encoding, .. } => *encoding,
+    }
+  }
+
+  /// Returns the number of samples, counting whole samples only.
+  pub fn len(&self) -> usize {
+    match self {
+      AudioBuffer::U8(samples) => samples.len(),
+      AudioBuffer::I16(samples) => samples.len(),
+      AudioBuffer::I32(samples) => samples.len(),
+      AudioBuffer::F32(samples) => samples.len(),
+      AudioBuffer::F64(samples) => samples.len(),
+      AudioBuffer::Bytes { encoding, data, .. } => data.len() / encoding.bytes_per_sample(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Decodes every sample to a value between -1 and 1.
+  fn decode(&self) -> Result<Vec<f64>, SnowboyError> {
+    Ok(match *self {
+      AudioBuffer::U8(samples) => samples.iter().map(|&sample| decode_u8(sample)).collect(),
+      AudioBuffer::I16(samples) => samples.iter().map(|&sample| f64::from(sample) / 32_768.0).collect(),
+      AudioBuffer::I32(samples) => samples.iter().map(|&sample| f64::from(sample) / 2_147_483_648.0).collect(),
+      AudioBuffer::F32(samples) => samples.iter().map(|&sample| f64::from(sample)).collect(),
+      AudioBuffer::F64(samples) => samples.to_vec(),
+      AudioBuffer::Bytes { encoding, endian, data } => {
+        let size = encoding.bytes_per_sample();
+        if !data.len().is_multiple_of(size) {
+          return Err(SnowboyError::InvalidArgument(format!("{} bytes is not a whole number of {} bytes samples",
+                                                           data.len(), size)));
+        }
+        data.chunks_exact(size).map(|bytes| decode_bytes(encoding, endian, bytes)).collect()
+      }
+    })
+  }
+}
+
+impl<'a> From<&'a [u8]> for AudioBuffer<'a> {
+  fn from(samples: &'a [u8]) -> Self {
+    AudioBuffer::U8(samples)
+  }
+}
+
+impl<'a> From<&'a [i16]> for AudioBuffer<'a> {
+  fn from(samples: &'a [i16]) -> Self {
+    AudioBuffer::I16(samples)
+  }
+}
+
+impl<'a> From<&'a [i32]> for AudioBuffer<'a> {
+  fn from(samples: &'a [i32]) -> Self {
+    AudioBuffer::I32(samples)
+  }
+}
+
+impl<'a> From<&'a [f32]> for AudioBuffer<'a> {
+  fn from(samples: &'a [f32]) -> Self {
+    AudioBuffer::F32(samples)
+  }
+}
+
+impl<'a> From<&'a [f64]> for AudioBuffer<'a> {
+  fn from(samples: &'a [f64]) -> Self {
+    AudioBuffer::F64(samples)
+  }
+}
+
+/// Noise added when samples are narrowed to fewer bits, so the rounding error
+/// is not correlated with the signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dither {
+  /// Samples are only rounded.
+  None,
+  /// Triangular noise of plus or minus one step of the narrower format.
+  Triangular,
+}
+
+/// What happens to samples outside of the range of an integer format, -1 to 1
+/// for floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Clipping {
+  /// Samples are clipped to the nearest value.
+  Saturate,
+  /// The conversion fails with `SnowboyError::InvalidArgument`. 1 itself is
+  /// in range, and becomes the largest integer.
+  Error,
+}
+
+/// Converts `AudioBuffer`s to the samples of one format, as little endian
+/// bytes, e.g. for `SnowboyDetect::detect_bytes`.
+///
+/// Samples are neither dithered nor checked for clipping by default.
+#[derive(Debug, Clone)]
+pub struct Converter {
+  format: AudioFormat,
+  dither: Dither,
+  clipping: Clipping,
+  /// State of the noise generator used for dithering.
+  seed: u32,
+}
+
+impl Converter {
+  pub fn new(format: AudioFormat) -> Self {
+    Self { format, dither: Dither::None, clipping: Clipping::Saturate, seed: 0x9e37_79b9 }
+  }
+
+  pub fn dither(mut self, dither: Dither) -> Self {
+    self.dither = dither;
+    self
+  }
+
+  pub fn clipping(mut self, clipping: Clipping) -> Self {
+    self.clipping = clipping;
+    self
+  }
+
+  pub fn format(&self) -> AudioFormat {
+    self.format
+  }
+
+  /// Converts `buffer` and appends the samples to `output`. Nothing is
+  /// appended if an error is returned.
+  pub fn convert(&mut self, buffer: AudioBuffer, output: &mut Vec<u8>) -> Result<(), SnowboyError> {
+    let samples = buffer.decode()?;
+    let bits = u32::from(self.format.bits_per_sample);
+    let start = output.len();
+    output.reserve(samples.len() * self.format.bytes_per_sample());
+    let result = match (self.format.sample_format, bits) {
+      (SampleFormat::Int, 8) | (SampleFormat::Int, 16) | (SampleFormat::Int, 24) | (SampleFormat::Int, 32) => {
+        let dither = self.dither == Dither::Triangular && buffer.encoding().precision() > bits;
+        samples.iter().try_for_each(|&sample| {
+          let value = self.quantize(sample, bits, dither)?;
+          if bits == 8 {
+            output.push((value + 128) as u8);
+          } else {
+            output.extend_from_slice(&value.to_le_bytes()[..bits as usize / 8]);
+          }
+          Ok(())
+        })
+      }
+      (SampleFormat::Float, 32) => {
+        samples.iter().for_each(|&sample| output.extend_from_slice(&(sample as f32).to_le_bytes()));
+        Ok(())
+      }
+      (SampleFormat::Float, 64) => {
+        samples.iter().for_each(|&sample| output.extend_from_slice(&sample.to_le_bytes()));
+        Ok(())
+      }
+      _ => Err(SnowboyError::InvalidArgument(format!("can not convert samples to {}", self.format))),
+    };
+    if result.is_err() {
+      output.truncate(start);
+    }
+    result
+  }
+
+  /// Scales `sample` to a `bits` integer, rounded and clipped.
+  fn quantize(&mut self, sample: f64, bits: u32, dither: bool) -> Result<i32, SnowboyError> {
+    let scale = f64::from(1u32 << (bits - 1));
+    let scaled = sample * scale;
+    // Full scale floats, 1.0 included, are in range, and saturate to the
+    // largest integer.
+    if self.clipping == Clipping::Error && !(-1.0..=1.0).contains(&sample) {
+      return Err(SnowboyError::InvalidArgument(format!("the sample {} is out of range for {}-bits samples",
+                                                       sample, bits)));
+    }
+    let noise = if dither { self.random() - self.random() } else { 0.0 };
+    Ok((scaled + noise).round().max(-scale).min(scale - 1.0) as i32)
+  }
+
+  /// Returns a pseudo-random number between 0 and 1, from a xorshift
+  /// generator.
+  fn random(&mut self) -> f64 {
+    self.seed ^= self.seed << 13;
+    self.seed ^= self.seed >> 17;
+    self.seed ^= self.seed << 5;
+    f64::from(self.seed) / 4_294_967_296.0
+  }
+}
+
+fn decode_u8(sample: u8) -> f64 {
+  (f64::from(sample) - 128.0) / 128.0
+}
+
+fn decode_bytes(encoding: Encoding, endian: Endian, bytes: &[u8]) -> f64 {
+  let mut little = [0u8; 8];
+  little[..bytes.len()].copy_from_slice(bytes);
+  if endian == Endian::Big {
+    little[..bytes.len()].reverse();
+  }
+  match encoding {
+    Encoding::U8 => decode_u8(little[0]),
+    Encoding::I16 => f64::from(i16::from_le_bytes([little[0], little[1]])) / 32_768.0,
+    // Shifted to the top of an i32 to extend the sign.
+    Encoding::I24 => f64::from(i32::from_le_bytes([0, little[0], little[1], little[2]]) >> 8) / 8_388_608.0,
+    Encoding::I32 => f64::from(i32::from_le_bytes([little[0], little[1], little[2], little[3]])) / 2_147_483_648.0,
+    Encoding::F32 => f64::from(f32::from_le_bytes([little[0], little[1], little[2], little[3]])),
+    Encoding::F64 => f64::from_le_bytes(little),
+  }
+}
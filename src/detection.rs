@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+use std::num::NonZeroU32;
+
+use crate::error::SnowboyError;
+
+/// Result of running hotword detection over a chunk of audio.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DetectionResult {
+  /// The chunk is silence.
+  Silence,
+  /// The chunk contains sound, but no hotword was triggered.
+  Sound,
+  /// A hotword was triggered. `index` starts at 1 and follows the order of the
+  /// hotwords in the loaded models. `name` is the name of the hotword when the
+  /// detector was created from `HotwordModels`.
+  Hotword { index: NonZeroU32, name: Option<String> },
+}
+
+impl DetectionResult {
+  /// Converts a raw code returned by RunDetection(). `-1` is reported as an
+  /// error.
+  ///
+  /// ```
+  /// # use rsnowboy::DetectionResult;
+  ///
+  /// assert_eq!(DetectionResult::from_code(-2).unwrap(), DetectionResult::Silence);
+  /// assert_eq!(DetectionResult::from_code(0).unwrap(), DetectionResult::Sound);
+  /// assert!(DetectionResult::from_code(-1).is_err());
+  /// ```
+  pub fn from_code(code: i32) -> Result<Self, SnowboyError> {
+    match code {
+      -2 => Ok(DetectionResult::Silence),
+      0 => Ok(DetectionResult::Sound),
+      code if code > 0 => {
+        let index = NonZeroU32::new(code as u32).expect("positive code");
+        Ok(DetectionResult::Hotword { index, name: None })
+      }
+      code => Err(SnowboyError::from_code(code)),
+    }
+  }
+
+  /// Returns the raw code RunDetection() uses for this result.
+  pub fn code(&self) -> i32 {
+    match self {
+      DetectionResult::Silence => -2,
+      DetectionResult::Sound => 0,
+      DetectionResult::Hotword { index, .. } => i32::try_from(index.get()).unwrap_or(i32::MAX),
+    }
+  }
+
+  /// Returns the index of the triggered hotword, if any.
+  pub fn hotword(&self) -> Option<NonZeroU32> {
+    match self {
+      DetectionResult::Hotword { index, .. } => Some(*index),
+      _ => None,
+    }
+  }
+
+  /// Returns the name of the triggered hotword, if any and if it is known.
+  pub fn hotword_name(&self) -> Option<&str> {
+    match self {
+      DetectionResult::Hotword { name, .. } => name.as_deref(),
+      _ => None,
+    }
+  }
+}
+
+/// Result of running the VAD over a chunk of audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VadResult {
+  /// The chunk is silence.
+  Silence,
+  /// The chunk contains speech.
+  Speech,
+}
+
+impl VadResult {
+  /// Converts a raw code returned by RunVad(). `-1` is reported as an error.
+  ///
+  /// ```
+  /// # use rsnowboy::VadResult;
+  ///
+  /// assert_eq!(VadResult::from_code(-2).unwrap(), VadResult::Silence);
+  /// assert_eq!(VadResult::from_code(0).unwrap(), VadResult::Speech);
+  /// assert!(VadResult::from_code(-1).is_err());
+  /// ```
+  pub fn from_code(code: i32) -> Result<Self, SnowboyError> {
+    match code {
+      -2 => Ok(VadResult::Silence),
+      0 => Ok(VadResult::Speech),
+      code => Err(SnowboyError::from_code(code)),
+    }
+  }
+
+  /// Returns the raw code RunVad() uses for this result.
+  pub fn code(self) -> i32 {
+    match self {
+      VadResult::Silence => -2,
+      VadResult::Speech => 0,
+    }
+  }
+}
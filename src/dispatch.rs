@@ -0,0 +1,248 @@
+//! Callbacks for detection events.
+//!
+//! `Dispatcher` is the Rust side of the Node binding's `on('hotword', ...)`:
+//! handlers are registered for hotwords, sound, silence, errors or any event,
+//! and every event passed to `Dispatcher::dispatch` is handed to the handlers
+//! that match it. Events can come from `SnowboyDetect::events`, a
+//! `DetectorSink` (see `Dispatcher::handler`), or the channel of a `Listener`.
+//!
+//! Each registration returns a `Subscription`, which removes the handler when
+//! it is dropped. A handler that panics does not stop the others, nor the
+//! code that dispatches the events.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//!
+//! use rsnowboy::dispatch::Dispatcher;
+//! use rsnowboy::{HotwordModel, HotwordModels, SnowboyDetect};
+//!
+//! let mut models = HotwordModels::new();
+//! models.add(HotwordModel::new("resources/models/snowboy.umdl", vec!["snowboy"]))?;
+//! let mut detector = SnowboyDetect::with_models("resources/common.res", models)?;
+//! let dispatcher = Dispatcher::new();
+//! let _hotword = dispatcher.on_hotword("snowboy", |event| println!("Snowboy at {:?}", event.timestamp));
+//! let _error = dispatcher.on_error(|error| eprintln!("Detection failed: {}", error));
+//!
+//! let mut sink = detector.sink(dispatcher.handler())?;
+//! io::copy(&mut io::stdin(), &mut sink)?;
+//! sink.finish()?;
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+use crate::error::SnowboyError;
+use crate::events::{DetectionEvent, EventKind};
+
+/// Which hotword a handler of `Dispatcher::on_hotword` is for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Hotword {
+  /// Any hotword.
+  Any,
+  /// The hotword of this index, counting from 1 in the order of the models.
+  Index(NonZeroU32),
+  /// The hotword of this name, known when the detector was created from
+  /// `HotwordModels`.
+  Name(String),
+}
+
+impl Hotword {
+  fn matches(&self, event: &DetectionEvent) -> bool {
+    match self {
+      Hotword::Any => true,
+      Hotword::Index(index) => event.hotword_index == Some(*index),
+      Hotword::Name(name) => event.hotword_name.as_deref() == Some(name.as_str()),
+    }
+  }
+}
+
+impl From<NonZeroU32> for Hotword {
+  fn from(index: NonZeroU32) -> Self {
+    Hotword::Index(index)
+  }
+}
+
+impl From<&str> for Hotword {
+  fn from(name: &str) -> Self {
+    Hotword::Name(name.to_string())
+  }
+}
+
+impl From<String> for Hotword {
+  fn from(name: String) -> Self {
+    Hotword::Name(name)
+  }
+}
+
+type EventHandler = dyn FnMut(&DetectionEvent) + Send;
+type ErrorHandler = dyn FnMut(&SnowboyError) + Send;
+type AnyHandler = dyn FnMut(&Result<DetectionEvent, SnowboyError>) + Send;
+
+/// A registered handler. Each one has a lock of its own, so that handlers run
+/// without the lock of the registry, and may register or remove handlers.
+#[derive(Clone)]
+enum Handler {
+  Hotword(Hotword, Arc<Mutex<Box<EventHandler>>>),
+  Sound(Arc<Mutex<Box<EventHandler>>>),
+  Silence(Arc<Mutex<Box<EventHandler>>>),
+  Error(Arc<Mutex<Box<ErrorHandler>>>),
+  Any(Arc<Mutex<Box<AnyHandler>>>),
+}
+
+impl Handler {
+  /// Calls the handler with `event` if it matches. Returns whether the
+  /// handler panicked.
+  fn call(&self, event: &Result<DetectionEvent, SnowboyError>) -> bool {
+    match (self, event) {
+      (Handler::Hotword(hotword, handler), Ok(event)) if event.kind == EventKind::Hotword && hotword.matches(event) => {
+        isolate(handler, |handler| handler(event))
+      }
+      (Handler::Sound(handler), Ok(event)) if matches!(event.kind, EventKind::Sound | EventKind::SoundStart) => {
+        isolate(handler, |handler| handler(event))
+      }
+      (Handler::Silence(handler), Ok(event)) if matches!(event.kind, EventKind::Silence | EventKind::SoundEnd) => {
+        isolate(handler, |handler| handler(event))
+      }
+      (Handler::Error(handler), Err(error)) => isolate(handler, |handler| handler(error)),
+      (Handler::Any(handler), event) => isolate(handler, |handler| handler(event)),
+      _ => false,
+    }
+  }
+}
+
+/// Runs `call` with the handler behind `handler`, and catches its panic.
+fn isolate<H: ?Sized, F: FnOnce(&mut H)>(handler: &Mutex<Box<H>>, call: F) -> bool {
+  // A panic is caught before it leaves the lock, which is never poisoned.
+  let mut handler = handler.lock().unwrap_or_else(PoisonError::into_inner);
+  panic::catch_unwind(AssertUnwindSafe(|| call(&mut **handler))).is_err()
+}
+
+#[derive(Default)]
+struct Registry {
+  next_id: u64,
+  handlers: Vec<(u64, Handler)>,
+}
+
+/// Calls the handlers registered for each event it is given.
+///
+/// Clones share their handlers, so that events can be dispatched from another
+/// thread, e.g. by a `DetectorSink` there.
+#[derive(Clone, Default)]
+pub struct Dispatcher {
+  registry: Arc<Mutex<Registry>>,
+}
+
+impl Dispatcher {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Calls `handler` when `hotword` is detected: `Hotword::Any`, an index
+  /// such as `NonZeroU32::new(1).unwrap()`, or a name such as `"snowboy"`.
+  pub fn on_hotword<H, F>(&self, hotword: H, handler: F) -> Subscription
+    where H: Into<Hotword>, F: FnMut(&DetectionEvent) + Send + 'static {
+    self.register(Handler::Hotword(hotword.into(), Arc::new(Mutex::new(Box::new(handler)))))
+  }
+
+  /// Calls `handler` for every chunk with sound but no hotword, and for
+  /// `EventKind::SoundStart`.
+  pub fn on_sound<F>(&self, handler: F) -> Subscription
+    where F: FnMut(&DetectionEvent) + Send + 'static {
+    self.register(Handler::Sound(Arc::new(Mutex::new(Box::new(handler)))))
+  }
+
+  /// Calls `handler` for every chunk of silence, and for
+  /// `EventKind::SoundEnd`.
+  pub fn on_silence<F>(&self, handler: F) -> Subscription
+    where F: FnMut(&DetectionEvent) + Send + 'static {
+    self.register(Handler::Silence(Arc::new(Mutex::new(Box::new(handler)))))
+  }
+
+  /// Calls `handler` for every error.
+  pub fn on_error<F>(&self, handler: F) -> Subscription
+    where F: FnMut(&SnowboyError) + Send + 'static {
+    self.register(Handler::Error(Arc::new(Mutex::new(Box::new(handler)))))
+  }
+
+  /// Calls `handler` for every event and error.
+  pub fn on_any<F>(&self, handler: F) -> Subscription
+    where F: FnMut(&Result<DetectionEvent, SnowboyError>) + Send + 'static {
+    self.register(Handler::Any(Arc::new(Mutex::new(Box::new(handler)))))
+  }
+
+  /// Calls the handlers that match `event`, in the order they were
+  /// registered, and returns the number of them that panicked.
+  ///
+  /// Handlers registered or removed by a handler take effect from the next
+  /// event.
+  pub fn dispatch(&self, event: &Result<DetectionEvent, SnowboyError>) -> usize {
+    let handlers = self.lock().handlers.iter().map(|(_, handler)| handler.clone()).collect::<Vec<_>>();
+    handlers.iter().filter(|handler| handler.call(event)).count()
+  }
+
+  /// Returns a callback that dispatches the events it is called with, e.g. for
+  /// `SnowboyDetect::sink`.
+  pub fn handler(&self) -> impl FnMut(Result<DetectionEvent, SnowboyError>) + Send + 'static {
+    let dispatcher = self.clone();
+    move |event| {
+      dispatcher.dispatch(&event);
+    }
+  }
+
+  /// Returns the number of registered handlers.
+  pub fn len(&self) -> usize {
+    self.lock().handlers.len()
+  }
+
+  /// Returns whether no handler is registered.
+  pub fn is_empty(&self) -> bool {
+    self.lock().handlers.is_empty()
+  }
+
+  fn register(&self, handler: Handler) -> Subscription {
+    let mut registry = self.lock();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    registry.handlers.push((id, handler));
+    Subscription { registry: Arc::downgrade(&self.registry), id }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Registry> {
+    self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+impl fmt::Debug for Dispatcher {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Dispatcher").field("handlers", &self.len()).finish()
+  }
+}
+
+/// A registered handler, which is removed when the subscription is dropped.
+#[must_use = "the handler is removed when the subscription is dropped"]
+#[derive(Debug)]
+pub struct Subscription {
+  registry: Weak<Mutex<Registry>>,
+  id: u64,
+}
+
+impl Subscription {
+  /// Keeps the handler for as long as the dispatcher lives.
+  pub fn detach(self) {
+    std::mem::forget(self);
+  }
+}
+
+impl Drop for Subscription {
+  fn drop(&mut self) {
+    if let Some(registry) = self.registry.upgrade() {
+      let mut registry = registry.lock().unwrap_or_else(PoisonError::into_inner);
+      registry.handlers.retain(|(id, _)| *id != self.id);
+    }
+  }
+}
@@ -0,0 +1,10 @@
+//! The resource and model bundled with the crate, embedded in the binary.
+//!
+//! Available with the `embedded-resources` feature, and loaded by
+//! `SnowboyDetect::embedded` and `SnowboyVad::embedded`.
+
+/// resources/common.res
+pub const COMMON_RES: &[u8] = include_bytes!("../resources/common.res");
+
+/// resources/models/snowboy.umdl, with the single hotword "snowboy".
+pub const SNOWBOY_UMDL: &[u8] = include_bytes!("../resources/models/snowboy.umdl");
@@ -0,0 +1,134 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt;
+use std::io;
+use std::num::NonZeroU32;
+use std::os::raw::c_int;
+use std::path::PathBuf;
+
+use crate::format::AudioFormat;
+use crate::rawrsnoboy::root as rsnowboy;
+
+/// Errors returned by the snowboy wrapper.
+#[derive(Debug)]
+pub enum SnowboyError {
+  /// The resource file, or one of the model files, is missing or can not be
+  /// read.
+  Resource { path: PathBuf, source: io::Error },
+  /// snowboy rejected a model file.
+  InvalidModel { path: PathBuf, reason: String },
+  /// The models declare a different number of hotwords than snowboy loaded
+  /// from them.
+  HotwordCount { declared: usize, loaded: usize },
+  /// The number of sensitivities does not match the number of hotwords.
+  SensitivityCount { expected: usize, found: usize },
+  /// A sensitivity is not between 0 and 1.
+  InvalidSensitivity(f32),
+  /// There is no hotword with this index.
+  HotwordIndex { index: NonZeroU32, num_hotwords: usize },
+  /// A path can not be passed to snowboy, e.g. it contains a comma.
+  InvalidPath { path: PathBuf, reason: String },
+  /// An argument can not be passed to snowboy, e.g. it contains a NUL byte.
+  InvalidArgument(String),
+  /// The number of samples is not a whole number of frames for the number of
+  /// channels the detector expects.
+  PartialFrame { samples: usize, channels: usize },
+  /// The buffer holds more samples than snowboy can take in one call.
+  BufferTooLarge { samples: usize },
+  /// Reading or writing audio failed.
+  Io(io::Error),
+  /// A WAV file is malformed, or uses an encoding that is not supported.
+  InvalidWav(String),
+  /// Audio does not have the format the detector expects.
+  FormatMismatch { expected: AudioFormat, found: AudioFormat },
+  /// snowboy failed internally.
+  Internal(String),
+}
+
+impl SnowboyError {
+  /// Error for a return code snowboy uses to report a failure.
+  pub(crate) fn from_code(code: i32) -> Self {
+    match code {
+      -1 => SnowboyError::Internal("snowboy reported an error".to_string()),
+      code => SnowboyError::Internal(format!("snowboy returned unexpected code {}", code)),
+    }
+  }
+}
+
+impl fmt::Display for SnowboyError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SnowboyError::Resource { path, source } => {
+        write!(f, "{}: {}", path.display(), source)
+      }
+      SnowboyError::InvalidModel { path, reason } => {
+        write!(f, "invalid model {}: {}", path.display(), reason)
+      }
+      SnowboyError::HotwordCount { declared, loaded } => {
+        write!(f, "the models declare {} hotwords, but snowboy loaded {}", declared, loaded)
+      }
+      SnowboyError::SensitivityCount { expected, found } => {
+        write!(f, "expected {} sensitivities, one per hotword, found {}", expected, found)
+      }
+      SnowboyError::InvalidSensitivity(value) => {
+        write!(f, "sensitivity {} is not between 0 and 1", value)
+      }
+      SnowboyError::HotwordIndex { index, num_hotwords } => {
+        write!(f, "hotword index {} is out of range, there are {} hotwords", index, num_hotwords)
+      }
+      SnowboyError::InvalidPath { path, reason } => write!(f, "invalid path {}: {}", path.display(), reason),
+      SnowboyError::InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+      SnowboyError::PartialFrame { samples, channels } => {
+        write!(f, "{} samples is not a whole number of frames for {} channels", samples, channels)
+      }
+      SnowboyError::BufferTooLarge { samples } => {
+        write!(f, "{} samples is more than snowboy can process at once", samples)
+      }
+      SnowboyError::Io(source) => write!(f, "{}", source),
+      SnowboyError::InvalidWav(reason) => write!(f, "invalid WAV file: {}", reason),
+      SnowboyError::FormatMismatch { expected, found } => {
+        write!(f, "expected audio in {}, found {}", expected, found)
+      }
+      SnowboyError::Internal(message) => write!(f, "{}", message),
+    }
+  }
+}
+
+impl From<Infallible> for SnowboyError {
+  fn from(never: Infallible) -> Self {
+    match never {}
+  }
+}
+
+impl From<io::Error> for SnowboyError {
+  fn from(error: io::Error) -> Self {
+    SnowboyError::Io(error)
+  }
+}
+
+impl Error for SnowboyError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      SnowboyError::Resource { source, .. } => Some(source),
+      SnowboyError::Io(source) => Some(source),
+      _ => None,
+    }
+  }
+}
+
+/// Calls a wrapper function with an error slot, and turns the exception it
+/// caught, if any, into a `SnowboyError::Internal`.
+pub(crate) fn catch<T, F>(call: F) -> Result<T, SnowboyError>
+  where F: FnOnce(*mut rsnowboy::RSnowboyError) -> T {
+  let mut error = rsnowboy::RSnowboyError {
+    code: rsnowboy::RSNOWBOY_OK as c_int,
+    message: [0; rsnowboy::RSNOWBOY_ERROR_MESSAGE_SIZE as usize],
+  };
+  let value = call(&mut error);
+  if error.code == rsnowboy::RSNOWBOY_OK as c_int {
+    return Ok(value);
+  }
+  let message = unsafe { CStr::from_ptr(error.message.as_ptr()) };
+  Err(SnowboyError::Internal(message.to_string_lossy().trim().to_string()))
+}
@@ -0,0 +1,193 @@
+//! Detection events with their position in the stream.
+//!
+//! `SnowboyDetect::events` runs hotword detection over an `AudioSource` as it
+//! is read, and returns an iterator of `DetectionEvent`s, each with the offset
+//! of the chunk it was found in, in samples and as a duration from the start
+//! of the stream.
+//!
+//! By default there is one event for every chunk of 100 ms. With
+//! `Events::transitions`, runs of silence and sound are squashed into
+//! `SoundStart` and `SoundEnd` events, which suits indexing recordings.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rsnowboy::events::EventKind;
+//! use rsnowboy::wav::WavReader;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let source = WavReader::open("recording.wav")?;
+//! for event in detector.events(source)?.transitions() {
+//!   let event = event?;
+//!   match event.kind {
+//!     EventKind::Hotword => println!("{:?}: hotword {:?}", event.timestamp, event.hotword_index),
+//!     kind => println!("{:?}: {:?}", event.timestamp, kind),
+//!   }
+//! }
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use crate::detection::DetectionResult;
+use crate::error::SnowboyError;
+use crate::snowboy::SnowboyDetect;
+use crate::source::{AudioSource, ChunkReader};
+
+/// What happened in the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+  /// The chunk is silence.
+  Silence,
+  /// The chunk contains sound, but no hotword was triggered.
+  Sound,
+  /// A hotword was triggered in the chunk.
+  Hotword,
+  /// Sound started after silence, or at the start of the stream. Only
+  /// reported by `Events::transitions`.
+  SoundStart,
+  /// Silence started after sound, or the stream ended during sound. Only
+  /// reported by `Events::transitions`.
+  SoundEnd,
+}
+
+/// A detection result, or a transition, at a position in the stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetectionEvent {
+  pub kind: EventKind,
+  /// Index of the hotword, for `EventKind::Hotword` events.
+  pub hotword_index: Option<NonZeroU32>,
+  /// Name of the hotword, when the detector knows it.
+  pub hotword_name: Option<String>,
+  /// Offset of the start of the chunk, in samples per channel from the start
+  /// of the stream.
+  pub sample_offset: u64,
+  /// `sample_offset` as a duration, from the sample rate of the detector.
+  pub timestamp: Duration,
+}
+
+impl DetectionEvent {
+  /// Creates the event of `kind` for `result`, found at `sample_offset` in a
+  /// stream at `sample_rate`.
+  pub(crate) fn new(kind: EventKind, result: &DetectionResult, sample_offset: u64, sample_rate: u32) -> Self {
+    let (hotword_index, hotword_name) = match kind {
+      EventKind::Hotword => (result.hotword(), result.hotword_name().map(str::to_string)),
+      _ => (None, None),
+    };
+    let nanos = u128::from(sample_offset) * 1_000_000_000 / u128::from(sample_rate.max(1));
+    Self { kind, hotword_index, hotword_name, sample_offset, timestamp: Duration::from_nanos(nanos as u64) }
+  }
+
+  /// Creates the `Silence`, `Sound` or `Hotword` event for `result`.
+  pub(crate) fn from_result(result: &DetectionResult, sample_offset: u64, sample_rate: u32) -> Self {
+    let kind = match result {
+      DetectionResult::Silence => EventKind::Silence,
+      DetectionResult::Sound => EventKind::Sound,
+      DetectionResult::Hotword { .. } => EventKind::Hotword,
+    };
+    Self::new(kind, result, sample_offset, sample_rate)
+  }
+}
+
+/// Iterator of the events of an `AudioSource`, see `SnowboyDetect::events`.
+///
+/// The source is read in chunks of 100 ms as the iterator advances. It stops
+/// after the end of the source, or after the first error.
+#[derive(Debug)]
+pub struct Events<'a, A> {
+  detector: &'a mut SnowboyDetect,
+  source: A,
+  reader: ChunkReader,
+  transitions: bool,
+  /// Whether the last chunk contained sound, for `transitions`.
+  sound: bool,
+  pending: VecDeque<DetectionEvent>,
+  done: bool,
+}
+
+impl<'a, A: AudioSource> Events<'a, A> {
+  pub(crate) fn new(detector: &'a mut SnowboyDetect, source: A) -> Self {
+    let reader = ChunkReader::new(source.format());
+    Self { detector, source, reader, transitions: false, sound: false, pending: VecDeque::new(), done: false }
+  }
+
+  /// Reports `SoundStart` and `SoundEnd` when the stream goes from silence to
+  /// sound and back, rather than `Silence` and `Sound` for every chunk.
+  /// Hotwords are always reported, and count as sound.
+  pub fn transitions(mut self) -> Self {
+    self.transitions = true;
+    self
+  }
+
+  /// Returns the source, e.g. to read what is left after a hotword.
+  pub fn into_source(self) -> A {
+    self.source
+  }
+
+  /// Resets the detector, so that what comes next is heard afresh, e.g. after
+  /// a pause. The offsets go on from where they were.
+  pub(crate) fn reset_detector(&mut self) -> Result<bool, SnowboyError> {
+    self.detector.reset()
+  }
+
+  /// Runs detection over the next chunk, and queues its events.
+  fn step(&mut self) -> Result<(), SnowboyError> {
+    let detector = &mut *self.detector;
+    let chunk = self.reader.read(&mut self.source, |chunk, is_end| detector.detect_bytes(chunk, is_end))?;
+    let (result, sample_offset) = (&chunk.result, chunk.sample_offset);
+    self.done = chunk.is_end;
+
+    if chunk.frames == 0 {
+      // The empty chunk that ends a stream on a chunk boundary only tells
+      // about a hotword.
+      if result.hotword().is_some() {
+        self.push(EventKind::Hotword, result, sample_offset);
+      }
+    } else if !self.transitions {
+      let sample_rate = self.source.format().sample_rate;
+      self.pending.push_back(DetectionEvent::from_result(result, sample_offset, sample_rate));
+    } else {
+      let sound = *result != DetectionResult::Silence;
+      if sound != self.sound {
+        let kind = if sound { EventKind::SoundStart } else { EventKind::SoundEnd };
+        self.push(kind, result, sample_offset);
+        self.sound = sound;
+      }
+      if result.hotword().is_some() {
+        self.push(EventKind::Hotword, result, sample_offset);
+      }
+    }
+
+    if chunk.is_end && self.transitions && self.sound {
+      self.push(EventKind::SoundEnd, result, self.reader.sample_offset());
+    }
+    Ok(())
+  }
+
+  fn push(&mut self, kind: EventKind, result: &DetectionResult, sample_offset: u64) {
+    let sample_rate = self.source.format().sample_rate;
+    self.pending.push_back(DetectionEvent::new(kind, result, sample_offset, sample_rate));
+  }
+}
+
+impl<'a, A: AudioSource> Iterator for Events<'a, A> {
+  type Item = Result<DetectionEvent, SnowboyError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(event) = self.pending.pop_front() {
+        return Some(Ok(event));
+      }
+      if self.done {
+        return None;
+      }
+      if let Err(error) = self.step() {
+        self.done = true;
+        return Some(Err(error));
+      }
+    }
+  }
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+
+/// How the samples of an audio stream are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleFormat {
+  /// Linear PCM integers. 8-bits samples are unsigned, wider ones signed.
+  Int,
+  /// IEEE floating point.
+  Float,
+}
+
+/// Format of an audio stream: what a detector expects, or what a WAV file
+/// holds.
+///
+/// # Examples
+///
+/// ```
+/// # use rsnowboy::{AudioFormat, SampleFormat};
+///
+/// let format = AudioFormat::new(16000, 1, 16, SampleFormat::Int);
+/// assert_eq!(format.bytes_per_frame(), 2);
+/// assert_eq!(format.to_string(), "16000 Hz, 1 channel, 16-bits int");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AudioFormat {
+  pub sample_rate: u32,
+  pub num_channels: u16,
+  pub bits_per_sample: u16,
+  pub sample_format: SampleFormat,
+}
+
+impl AudioFormat {
+  pub fn new(sample_rate: u32, num_channels: u16, bits_per_sample: u16, sample_format: SampleFormat) -> Self {
+    Self { sample_rate, num_channels, bits_per_sample, sample_format }
+  }
+
+  /// Format of the audio snowboy expects, from SampleRate(), NumChannels() and
+  /// BitsPerSample().
+  pub(crate) fn from_snowboy(sample_rate: i32, num_channels: i32, bits_per_sample: i32) -> Self {
+    Self::new(sample_rate.max(0) as u32, num_channels.max(0) as u16, bits_per_sample.max(0) as u16,
+              SampleFormat::Int)
+  }
+
+  pub fn bytes_per_sample(&self) -> usize {
+    (self.bits_per_sample as usize).div_ceil(8)
+  }
+
+  /// Returns the size of one sample for every channel.
+  pub fn bytes_per_frame(&self) -> usize {
+    self.bytes_per_sample() * self.num_channels as usize
+  }
+}
+
+impl fmt::Display for AudioFormat {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let channels = if self.num_channels == 1 { "channel" } else { "channels" };
+    let sample_format = match self.sample_format {
+      SampleFormat::Int => "int",
+      SampleFormat::Float => "float",
+    };
+    write!(f, "{} Hz, {} {}, {}-bits {}", self.sample_rate, self.num_channels, channels, self.bits_per_sample,
+           sample_format)
+  }
+}
@@ -0,0 +1,212 @@
+//! Hotword detection on a background thread.
+//!
+//! A `Listener` owns a detector and an `AudioSource`, e.g. a `CommandSource`
+//! recording from a microphone, and runs detection on a thread of its own
+//! until the source ends or the listener is stopped. The events of
+//! `SnowboyDetect::events`, one for every chunk of 100 ms, are sent to a
+//! channel.
+//!
+//! Errors are sent to the channel too, and end the thread.
+//!
+//! The thread checks whether it is paused or stopped between chunks, so it
+//! only notices once the chunk it is reading is there. A source that records
+//! nothing, e.g. a command waiting for a device, holds it up: kill the command
+//! with `CommandSource::kill_handle` to stop it at once.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::process::Command;
+//! use std::sync::mpsc;
+//!
+//! use rsnowboy::events::EventKind;
+//! use rsnowboy::listener::Listener;
+//! use rsnowboy::source::CommandSource;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let mut command = Command::new("arecord");
+//! command.args(["-q", "-t", "raw", "-f", "S16_LE", "-r", "16000", "-c", "1"]);
+//! let source = CommandSource::spawn(command, detector.format())?;
+//! let kill = source.kill_handle();
+//!
+//! let (sender, receiver) = mpsc::channel();
+//! let listener = Listener::spawn(detector, source, sender)?;
+//! for event in receiver.iter() {
+//!   match event {
+//!     Ok(event) if event.kind == EventKind::Hotword => {
+//!       println!("Hotword {:?} detected", event.hotword_index);
+//!       break;
+//!     }
+//!     Ok(_) => {}
+//!     Err(error) => eprintln!("Detection failed: {}", error),
+//!   }
+//! }
+//! kill.kill()?;
+//! let detector = listener.stop();
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::panic;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+
+use crate::error::SnowboyError;
+use crate::events::{DetectionEvent, Events};
+use crate::snowboy::{self, SnowboyDetect};
+use crate::source::AudioSource;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+  Running,
+  Paused,
+  Stopped,
+}
+
+/// State of the listener, shared with its thread.
+#[derive(Debug)]
+struct Control {
+  state: Mutex<State>,
+  changed: Condvar,
+}
+
+impl Control {
+  fn lock(&self) -> MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  /// Moves to `state`, unless the listener is stopped.
+  fn set(&self, state: State) {
+    let mut current = self.lock();
+    if *current != State::Stopped {
+      *current = state;
+    }
+    self.changed.notify_all();
+  }
+
+  /// Waits while the listener is paused. Returns the state, and whether the
+  /// listener was paused.
+  fn wait(&self) -> (State, bool) {
+    let mut state = self.lock();
+    let mut paused = false;
+    while *state == State::Paused {
+      paused = true;
+      state = self.changed.wait(state).unwrap_or_else(PoisonError::into_inner);
+    }
+    (*state, paused)
+  }
+}
+
+/// Runs hotword detection over an `AudioSource` on a background thread.
+///
+/// Dropping the listener stops it without waiting for its thread, which ends
+/// once the chunk it is reading is there.
+#[derive(Debug)]
+pub struct Listener {
+  control: Arc<Control>,
+  thread: Option<JoinHandle<SnowboyDetect>>,
+}
+
+impl Listener {
+  /// Starts listening to `source`, which must have the format of the
+  /// detector, see `SnowboyDetect::format`, and sends every event to
+  /// `sender`.
+  ///
+  /// The thread ends at the end of the source, after an error, or once the
+  /// receiver is dropped.
+  pub fn spawn<A>(detector: SnowboyDetect, source: A, sender: Sender<Result<DetectionEvent, SnowboyError>>)
+                  -> Result<Self, SnowboyError>
+    where A: AudioSource + Send + 'static {
+    snowboy::check_source_format(&source, detector.format())?;
+    let control = Arc::new(Control { state: Mutex::new(State::Running), changed: Condvar::new() });
+    let shared = control.clone();
+    let thread = thread::Builder::new()
+      .name("snowboy-listener".to_string())
+      .spawn(move || {
+        let mut detector = detector;
+        listen(Events::new(&mut detector, source), &sender, &shared);
+        detector
+      })?;
+    Ok(Self { control, thread: Some(thread) })
+  }
+
+  /// Stops reading the source until `resume`. Audio that a live source
+  /// records meanwhile waits in the source, e.g. in the pipe of a command.
+  pub fn pause(&self) {
+    self.control.set(State::Paused);
+  }
+
+  /// Goes on listening after `pause`, with the detector reset.
+  pub fn resume(&self) {
+    self.control.set(State::Running);
+  }
+
+  /// Returns whether the listener is paused.
+  pub fn is_paused(&self) -> bool {
+    *self.control.lock() == State::Paused
+  }
+
+  /// Returns whether the thread has ended, e.g. at the end of the source.
+  pub fn is_finished(&self) -> bool {
+    self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+  }
+
+  /// Stops listening, and returns the detector once the thread has ended.
+  ///
+  /// The thread ends once the chunk it is reading, if any, is there, so this
+  /// blocks for as long as the source does: up to 100 ms for a live source,
+  /// and until it is killed for a command that records nothing, see
+  /// `CommandSource::kill_handle`.
+  pub fn stop(mut self) -> SnowboyDetect {
+    self.control.set(State::Stopped);
+    self.join_thread()
+  }
+
+  /// Waits for the end of the source, and returns the detector.
+  pub fn join(mut self) -> SnowboyDetect {
+    self.join_thread()
+  }
+
+  fn join_thread(&mut self) -> SnowboyDetect {
+    let thread = self.thread.take().expect("the thread is only joined once");
+    thread.join().unwrap_or_else(|payload| panic::resume_unwind(payload))
+  }
+}
+
+impl Drop for Listener {
+  fn drop(&mut self) {
+    // The thread may be blocked on a read: let it end on its own.
+    if self.thread.take().is_some() {
+      self.control.set(State::Stopped);
+    }
+  }
+}
+
+/// Sends the events of `events` until the source ends, or the listener is
+/// stopped.
+fn listen<A: AudioSource>(mut events: Events<'_, A>, sender: &Sender<Result<DetectionEvent, SnowboyError>>,
+                          control: &Control) {
+  loop {
+    match control.wait() {
+      (State::Stopped, _) => return,
+      // What was heard before the pause has nothing to do with what comes
+      // after it.
+      (_, true) => {
+        if let Err(error) = events.reset_detector() {
+          let _ = sender.send(Err(error));
+          return;
+        }
+      }
+      _ => {}
+    }
+    match events.next() {
+      Some(event) => {
+        if sender.send(event).is_err() {
+          return;
+        }
+      }
+      None => return,
+    }
+  }
+}
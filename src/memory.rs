@@ -0,0 +1,106 @@
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::SnowboyError;
+
+/// A file holding bytes given in memory, for snowboy, which only loads
+/// resources and models by filename.
+///
+/// On Linux it is an anonymous memfd, reached through /proc/self/fd. Elsewhere,
+/// or if that fails, it is a temporary file only the current user can read,
+/// which is removed on drop.
+#[derive(Debug)]
+pub(crate) struct MemoryFile {
+  path: PathBuf,
+  temporary: bool,
+  // Keeps the memfd open for as long as snowboy may use its path.
+  _file: File,
+}
+
+impl MemoryFile {
+  /// Writes `bytes` to a new file. `name` describes the content, and is only
+  /// used in the name of the file and in errors.
+  pub(crate) fn new(name: &str, bytes: &[u8]) -> Result<Self, SnowboyError> {
+    let memory_error = |source| SnowboyError::Resource { path: PathBuf::from(format!("<{}>", name)), source };
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+      if let Ok(memory_file) = memfd(name, bytes) {
+        return Ok(memory_file);
+      }
+    }
+    temporary_file(name, bytes).map_err(memory_error)
+  }
+
+  pub(crate) fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for MemoryFile {
+  fn drop(&mut self) {
+    if self.temporary {
+      let _ = fs::remove_file(&self.path);
+    }
+  }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn memfd(name: &str, bytes: &[u8]) -> io::Result<MemoryFile> {
+  use std::ffi::CString;
+  use std::os::unix::io::FromRawFd;
+
+  let name = CString::new(format!("rsnowboy-{}", name)).expect("names have no NUL byte");
+  let fd = unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC) };
+  if fd < 0 {
+    return Err(io::Error::last_os_error());
+  }
+  let mut file = unsafe { File::from_raw_fd(fd) };
+  file.write_all(bytes)?;
+  let path = PathBuf::from(format!("/proc/self/fd/{}", fd));
+  // /proc may not be mounted, e.g. in a minimal container.
+  fs::metadata(&path)?;
+  Ok(MemoryFile { path, temporary: false, _file: file })
+}
+
+fn temporary_file(name: &str, bytes: &[u8]) -> io::Result<MemoryFile> {
+  let (mut file, path) = create_unique(&env::temp_dir(), name, open_private)?;
+  let memory_file = MemoryFile { path, temporary: true, _file: file.try_clone()? };
+  file.write_all(bytes)?;
+  file.sync_all()?;
+  Ok(memory_file)
+}
+
+/// Creates a file with a name no other file has in `dir`, ending with `name`.
+/// `open` must create the file with `create_new`, which fails rather than
+/// follow a file or link someone else put there, so a name that is taken is
+/// simply skipped.
+pub(crate) fn create_unique<F>(dir: &Path, name: &str, open: F) -> io::Result<(File, PathBuf)>
+  where F: Fn(&Path) -> io::Result<File> {
+  static COUNTER: AtomicUsize = AtomicUsize::new(0);
+  let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or(0);
+  loop {
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("rsnowboy-{}-{}-{}-{}", process::id(), nanos, count, name));
+    match open(&path) {
+      Ok(file) => return Ok((file, path)),
+      Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+      Err(error) => return Err(error),
+    }
+  }
+}
+
+#[cfg(unix)]
+fn open_private(path: &Path) -> io::Result<File> {
+  use std::os::unix::fs::OpenOptionsExt;
+  OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> io::Result<File> {
+  OpenOptions::new().write(true).create_new(true).open(path)
+}
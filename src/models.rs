@@ -0,0 +1,145 @@
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+use crate::error::SnowboyError;
+use crate::sensitivity::Sensitivities;
+
+/// Sensitivity given to a model when none is set, as in the Node binding.
+const DEFAULT_SENSITIVITY: f32 = 0.5;
+
+/// A hotword model file together with the names of the hotwords it holds, in
+/// the order snowboy numbers them.
+///
+/// A personal model (.pmdl) holds a single hotword, an universal model (.umdl)
+/// may hold several.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotwordModel {
+  file: PathBuf,
+  sensitivity: f32,
+  hotwords: Vec<String>,
+}
+
+impl HotwordModel {
+  /// Creates a model with the default sensitivity of 0.5.
+  pub fn new<P, I>(file: P, hotwords: I) -> Self
+    where P: AsRef<Path>, I: IntoIterator, I::Item: Into<String> {
+    Self {
+      file: file.as_ref().to_path_buf(),
+      sensitivity: DEFAULT_SENSITIVITY,
+      hotwords: hotwords.into_iter().map(Into::into).collect(),
+    }
+  }
+
+  /// Sets the sensitivity used for every hotword of this model.
+  pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+    self.sensitivity = sensitivity;
+    self
+  }
+
+  pub fn file(&self) -> &Path {
+    &self.file
+  }
+
+  pub fn sensitivity(&self) -> f32 {
+    self.sensitivity
+  }
+
+  pub fn hotwords(&self) -> &[String] {
+    &self.hotwords
+  }
+
+  fn is_personal(&self) -> bool {
+    self.file.extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("pmdl"))
+  }
+}
+
+/// The hotword models loaded by a detector, with the names of their hotwords.
+///
+/// This is the Rust counterpart of `HotwordModels` in the Node binding. It
+/// builds the model string and the sensitivities for `SnowboyDetect`, and maps
+/// the index of a triggered hotword back to its name.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use rsnowboy::{HotwordModel, HotwordModels, SnowboyDetect};
+/// use std::num::NonZeroU32;
+///
+/// let mut models = HotwordModels::new();
+/// models.add(HotwordModel::new("resources/models/snowboy.umdl", vec!["snowboy"]).with_sensitivity(0.6))?;
+/// assert_eq!(models.lookup(NonZeroU32::new(1).unwrap()), Some("snowboy"));
+///
+/// let detector = SnowboyDetect::with_models("resources/common.res", models)?;
+/// # Ok::<(), rsnowboy::SnowboyError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HotwordModels {
+  models: Vec<HotwordModel>,
+}
+
+impl HotwordModels {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds a model after the ones already added. An error is returned if the
+  /// file does not exist, if the model declares no hotword, if a personal
+  /// model declares more than one, or if the sensitivity is not between 0 and
+  /// 1.
+  pub fn add(&mut self, model: HotwordModel) -> Result<(), SnowboyError> {
+    fs::metadata(&model.file).map_err(|source| SnowboyError::Resource {
+      path: model.file.clone(),
+      source,
+    })?;
+    if model.hotwords.is_empty() {
+      return Err(SnowboyError::InvalidModel {
+        path: model.file.clone(),
+        reason: "no hotword is declared for the model".to_string(),
+      });
+    }
+    if model.is_personal() && model.hotwords.len() > 1 {
+      return Err(SnowboyError::InvalidModel {
+        path: model.file.clone(),
+        reason: "personal models can define only one hotword".to_string(),
+      });
+    }
+    Sensitivities::new(vec![model.sensitivity])?;
+    self.models.push(model);
+    Ok(())
+  }
+
+  pub fn models(&self) -> &[HotwordModel] {
+    &self.models
+  }
+
+  /// Returns the model files, in the order `SnowboyDetect::new` expects them.
+  pub fn files(&self) -> impl Iterator<Item = &Path> {
+    self.models.iter().map(|model| model.file.as_path())
+  }
+
+  /// Returns the sensitivities of all hotwords, in the order of their indices.
+  pub fn sensitivities(&self) -> Sensitivities {
+    let values = self.models.iter()
+      .flat_map(|model| model.hotwords.iter().map(move |_| model.sensitivity))
+      .collect::<Vec<f32>>();
+    Sensitivities::new(values).expect("sensitivities are checked when models are added")
+  }
+
+  /// Returns the name of hotword `index`. Indices start at 1, as in
+  /// `DetectionResult::Hotword`.
+  pub fn lookup(&self, index: NonZeroU32) -> Option<&str> {
+    self.hotwords().nth(index.get() as usize - 1)
+  }
+
+  /// Returns the number of hotwords declared by all models.
+  pub fn num_hotwords(&self) -> usize {
+    self.models.iter().map(|model| model.hotwords.len()).sum()
+  }
+
+  /// Returns the names of all hotwords, in the order of their indices.
+  pub fn hotwords(&self) -> impl Iterator<Item = &str> {
+    self.models.iter().flat_map(|model| model.hotwords.iter().map(String::as_str))
+  }
+}
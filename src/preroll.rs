@@ -0,0 +1,314 @@
+//! Audio from before a detection.
+//!
+//! snowboy reports a hotword once it was said, so the hotword and what came
+//! just before it are already gone when the result is known. `PreRoll` keeps
+//! the last few seconds of the stream, to get them back.
+//!
+//! The buffer is lock-free: `PreRoll::split` turns it into a `Producer`, for
+//! the thread that captures the audio, and a `Consumer`, for the one that
+//! acts on detections. Neither ever waits for the other.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rsnowboy::preroll::PreRoll;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let mut pre_roll = PreRoll::for_format(2000, detector.format())?;
+//! loop {
+//!   let chunk: Vec<i16> = vec![0; 1600]; // get voice from your device
+//!   // Push the chunk first, so the snapshot ends with it.
+//!   pre_roll.push(&chunk)?;
+//!   if detector.detect(&chunk, false)?.hotword().is_some() {
+//!     let snapshot = pre_roll.snapshot();
+//!     println!("hotword between samples {} and {}", snapshot.sample_offset, snapshot.end_offset);
+//!     break;
+//!   }
+//! }
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+//!
+//! With the audio captured on another thread:
+//!
+//! ```no_run
+//! use std::thread;
+//!
+//! use rsnowboy::preroll::PreRoll;
+//!
+//! let (mut producer, consumer) = PreRoll::<i16>::new(2000, 16000, 1)?.split();
+//! thread::spawn(move || loop {
+//!   let chunk: Vec<i16> = vec![0; 1600]; // get voice from your device
+//!   producer.push(&chunk).unwrap();
+//! });
+//! // Later, on a detection:
+//! let snapshot = consumer.snapshot();
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{self, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::error::SnowboyError;
+use crate::format::AudioFormat;
+use crate::sample::Sample;
+
+/// Audio copied out of a `PreRoll`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot<S> {
+  /// Interleaved samples, oldest first.
+  pub samples: Vec<S>,
+  /// Offset of the first frame from the start of the stream, in samples per
+  /// channel.
+  pub sample_offset: u64,
+  /// Offset of the frame after the last one, i.e. the number of frames
+  /// pushed so far.
+  pub end_offset: u64,
+}
+
+/// The samples and positions shared by the halves of a buffer.
+///
+/// Positions count samples from the start of the stream, and the sample at
+/// `position` is kept in slot `position % capacity`. Slots are atomics, so
+/// that the consumer may copy them while the producer overwrites the oldest
+/// ones: `claimed` is moved before slots are written and checked after they
+/// are copied, and the samples that were overwritten in between are dropped
+/// from the copy.
+struct Ring<S> {
+  channels: usize,
+  /// Bits of the samples. A whole number of frames.
+  slots: Box<[AtomicU32]>,
+  /// Position of the first sample kept, moved by `clear`. Samples before
+  /// `tail - capacity` are gone whatever its value.
+  head: AtomicU64,
+  /// Position after the last sample written.
+  tail: AtomicU64,
+  /// Position after the last sample being written.
+  claimed: AtomicU64,
+  samples: PhantomData<fn(S) -> S>,
+}
+
+impl<S: Sample> Ring<S> {
+  fn capacity(&self) -> u64 {
+    self.slots.len() as u64
+  }
+
+  /// Returns the positions of the samples kept.
+  fn range(&self) -> (u64, u64) {
+    let tail = self.tail.load(Ordering::Acquire);
+    (self.head.load(Ordering::Relaxed).max(tail.saturating_sub(self.capacity())), tail)
+  }
+
+  /// Appends `samples`. Only one thread may push at a time.
+  fn push(&self, samples: &[S]) -> Result<(), SnowboyError> {
+    if !samples.len().is_multiple_of(self.channels) {
+      return Err(SnowboyError::PartialFrame { samples: samples.len(), channels: self.channels });
+    }
+    let tail = self.tail.load(Ordering::Relaxed);
+    let end = tail + samples.len() as u64;
+    self.claimed.store(end, Ordering::Relaxed);
+    // Pairs with the fence of `snapshot`: a consumer that copies a slot
+    // written below also sees the claim.
+    atomic::fence(Ordering::Release);
+    // Only the end of a long slice would be kept.
+    let skipped = samples.len().saturating_sub(self.slots.len());
+    for (position, &sample) in (tail + skipped as u64..).zip(&samples[skipped..]) {
+      self.slots[(position % self.capacity()) as usize].store(sample.to_bits(), Ordering::Relaxed);
+    }
+    self.tail.store(end, Ordering::Release);
+    Ok(())
+  }
+
+  fn snapshot(&self) -> Snapshot<S> {
+    let (head, tail) = self.range();
+    let mut samples = (head..tail)
+      .map(|position| S::from_bits(self.slots[(position % self.capacity()) as usize].load(Ordering::Relaxed)))
+      .collect::<Vec<S>>();
+    atomic::fence(Ordering::Acquire);
+    // Drop what was overwritten while it was copied, a whole number of frames
+    // as every push is.
+    let overwritten = self.claimed.load(Ordering::Relaxed).saturating_sub(self.capacity()).clamp(head, tail);
+    samples.drain(..(overwritten - head) as usize);
+    let channels = self.channels as u64;
+    Snapshot { samples, sample_offset: overwritten / channels, end_offset: tail / channels }
+  }
+
+  fn clear(&self) {
+    self.head.store(self.tail.load(Ordering::Acquire), Ordering::Relaxed);
+  }
+}
+
+impl<S> Clone for Ring<S> {
+  fn clone(&self) -> Self {
+    let load = |position: &AtomicU64| AtomicU64::new(position.load(Ordering::Acquire));
+    Self {
+      channels: self.channels,
+      slots: self.slots.iter().map(|slot| AtomicU32::new(slot.load(Ordering::Relaxed))).collect(),
+      head: load(&self.head),
+      tail: load(&self.tail),
+      claimed: load(&self.claimed),
+      samples: PhantomData,
+    }
+  }
+}
+
+impl<S> fmt::Debug for Ring<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Ring")
+      .field("channels", &self.channels)
+      .field("capacity", &self.slots.len())
+      .field("head", &self.head)
+      .field("tail", &self.tail)
+      .finish()
+  }
+}
+
+/// Ring buffer of the most recent frames of a stream.
+///
+/// The buffer is allocated once, and old frames are overwritten in place.
+/// Used from a single thread, it pushes and reads; `split` shares it between
+/// a thread that pushes and one that reads, without a lock.
+#[derive(Debug, Clone)]
+pub struct PreRoll<S> {
+  ring: Ring<S>,
+}
+
+impl<S: Sample> PreRoll<S> {
+  /// Creates a buffer that keeps the last `millis` milliseconds of
+  /// `channels` interleaved channels at `sample_rate`.
+  pub fn new(millis: u32, sample_rate: u32, channels: usize) -> Result<Self, SnowboyError> {
+    let frames = (u64::from(sample_rate) * u64::from(millis) / 1000) as usize;
+    if frames == 0 || channels == 0 {
+      return Err(SnowboyError::InvalidArgument(format!("can not keep {} ms of {} channels at {} Hz",
+                                                       millis, channels, sample_rate)));
+    }
+    let ring = Ring {
+      channels,
+      slots: (0..frames * channels).map(|_| AtomicU32::new(0)).collect(),
+      head: AtomicU64::new(0),
+      tail: AtomicU64::new(0),
+      claimed: AtomicU64::new(0),
+      samples: PhantomData,
+    };
+    Ok(Self { ring })
+  }
+
+  /// Creates a buffer that keeps the last `millis` milliseconds of samples in
+  /// `format`, e.g. `SnowboyDetect::format`.
+  pub fn for_format(millis: u32, format: AudioFormat) -> Result<Self, SnowboyError> {
+    Self::new(millis, format.sample_rate, format.num_channels as usize)
+  }
+
+  /// Returns the number of frames the buffer can hold.
+  pub fn capacity_frames(&self) -> usize {
+    self.ring.slots.len() / self.ring.channels
+  }
+
+  /// Returns the number of frames the buffer holds.
+  pub fn len_frames(&self) -> usize {
+    let (head, tail) = self.ring.range();
+    (tail - head) as usize / self.ring.channels
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len_frames() == 0
+  }
+
+  /// Returns the offset of the oldest frame, in samples per channel from the
+  /// start of the stream.
+  pub fn sample_offset(&self) -> u64 {
+    self.ring.range().0 / self.ring.channels as u64
+  }
+
+  /// Returns the number of frames pushed since the start of the stream.
+  pub fn end_offset(&self) -> u64 {
+    self.ring.range().1 / self.ring.channels as u64
+  }
+
+  /// Appends `samples`, which must be whole frames, dropping the oldest
+  /// frames once the buffer is full.
+  pub fn push(&mut self, samples: &[S]) -> Result<(), SnowboyError> {
+    self.ring.push(samples)
+  }
+
+  /// Copies the samples out, with their offsets. When a chunk was pushed
+  /// before running detection on it, the snapshot ends with that chunk.
+  pub fn snapshot(&self) -> Snapshot<S> {
+    self.ring.snapshot()
+  }
+
+  /// Drops the samples, keeping the offset of the stream.
+  pub fn clear(&mut self) {
+    self.ring.clear();
+  }
+
+  /// Drops the samples, and starts a new stream at offset 0.
+  pub fn reset(&mut self) {
+    *self.ring.head.get_mut() = 0;
+    *self.ring.tail.get_mut() = 0;
+    *self.ring.claimed.get_mut() = 0;
+  }
+
+  /// Splits the buffer into the half that pushes and the half that reads,
+  /// which can be moved to different threads.
+  pub fn split(self) -> (Producer<S>, Consumer<S>) {
+    let ring = Arc::new(self.ring);
+    (Producer { ring: ring.clone() }, Consumer { ring })
+  }
+}
+
+/// The half of a `PreRoll` that pushes audio, see `PreRoll::split`.
+#[derive(Debug)]
+pub struct Producer<S> {
+  ring: Arc<Ring<S>>,
+}
+
+impl<S: Sample> Producer<S> {
+  /// Appends `samples`, see `PreRoll::push`. It never waits for the
+  /// `Consumer`.
+  pub fn push(&mut self, samples: &[S]) -> Result<(), SnowboyError> {
+    self.ring.push(samples)
+  }
+
+  /// Returns the number of frames pushed since the start of the stream.
+  pub fn end_offset(&self) -> u64 {
+    self.ring.tail.load(Ordering::Relaxed) / self.ring.channels as u64
+  }
+}
+
+/// The half of a `PreRoll` that reads audio, see `PreRoll::split`.
+#[derive(Debug)]
+pub struct Consumer<S> {
+  ring: Arc<Ring<S>>,
+}
+
+impl<S: Sample> Consumer<S> {
+  /// Returns the number of frames the buffer can hold.
+  pub fn capacity_frames(&self) -> usize {
+    self.ring.slots.len() / self.ring.channels
+  }
+
+  /// Returns the offset of the oldest frame, see `PreRoll::sample_offset`.
+  pub fn sample_offset(&self) -> u64 {
+    self.ring.range().0 / self.ring.channels as u64
+  }
+
+  /// Returns the number of frames pushed so far by the `Producer`.
+  pub fn end_offset(&self) -> u64 {
+    self.ring.range().1 / self.ring.channels as u64
+  }
+
+  /// Copies out the samples pushed so far, see `PreRoll::snapshot`. Frames
+  /// that the `Producer` overwrites during the copy are left out, so the
+  /// snapshot is shorter than the buffer when it falls behind.
+  pub fn snapshot(&self) -> Snapshot<S> {
+    self.ring.snapshot()
+  }
+
+  /// Drops the samples pushed so far, keeping the offset of the stream.
+  pub fn clear(&self) {
+    self.ring.clear();
+  }
+}
@@ -1,6 +1,6 @@
 /* automatically generated by rust-bindgen */
 
-#[allow(non_snake_case, non_camel_case_types, non_upper_case_globals)]
+#[allow(non_snake_case, non_camel_case_types, non_upper_case_globals, dead_code)]
 pub mod root {
     #[allow(unused_imports)]
     use self::super::root;
@@ -174,6 +174,10 @@ pub mod root {
     pub type uint_fast64_t = ::std::os::raw::c_ulong;
     pub type intmax_t = root::__intmax_t;
     pub type uintmax_t = root::__uintmax_t;
+    pub const RSNOWBOY_OK: u32 = 0;
+    pub const RSNOWBOY_EXCEPTION: u32 = 1;
+    pub const RSNOWBOY_UNKNOWN_EXCEPTION: u32 = 2;
+    pub const RSNOWBOY_ERROR_MESSAGE_SIZE: u32 = 512;
     #[repr(C)]
     #[derive(Debug, Copy, Clone)]
     pub struct RSnowboyDetect {
@@ -184,19 +188,32 @@ pub mod root {
     pub struct RSnowboyVad {
         _unused: [u8; 0],
     }
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct RSnowboyError {
+        pub code: ::std::os::raw::c_int,
+        pub message: [::std::os::raw::c_char; 512usize],
+    }
     extern "C" {
         pub fn detect_create(
             resource_filename: *const ::std::os::raw::c_char,
             model_str: *const ::std::os::raw::c_char,
+            error: *mut root::RSnowboyError,
         ) -> *mut root::RSnowboyDetect;
     }
     extern "C" {
-        pub fn detect_reset(detector: *mut root::RSnowboyDetect) -> bool;
+        pub fn detect_reset(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        ) -> bool;
     }
     extern "C" {
-        pub fn detect_run_char_detection(
+        pub fn detect_run_bytes_detection(
             detector: *mut root::RSnowboyDetect,
             data: *const ::std::os::raw::c_char,
+            length: usize,
+            is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
@@ -205,6 +222,7 @@ pub mod root {
             data: *const f32,
             array_length: ::std::os::raw::c_int,
             is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
@@ -213,6 +231,7 @@ pub mod root {
             data: *const i16,
             array_length: ::std::os::raw::c_int,
             is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
@@ -221,56 +240,100 @@ pub mod root {
             data: *const i32,
             array_length: ::std::os::raw::c_int,
             is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
         pub fn detect_set_sensitivity(
             detector: *mut root::RSnowboyDetect,
             sensitivity_str: *const ::std::os::raw::c_char,
+            error: *mut root::RSnowboyError,
+        );
+    }
+    extern "C" {
+        pub fn detect_set_high_sensitivity(
+            detector: *mut root::RSnowboyDetect,
+            high_sensitivity_str: *const ::std::os::raw::c_char,
+            error: *mut root::RSnowboyError,
         );
     }
     extern "C" {
         pub fn detect_get_sensitivity(
             detector: *mut root::RSnowboyDetect,
-        ) -> *const ::std::os::raw::c_char;
+            buffer: *mut ::std::os::raw::c_char,
+            buffer_size: usize,
+            error: *mut root::RSnowboyError,
+        ) -> usize;
     }
     extern "C" {
-        pub fn detect_set_audio_gain(detector: *mut root::RSnowboyDetect, audio_gain: f32);
+        pub fn detect_set_audio_gain(
+            detector: *mut root::RSnowboyDetect,
+            audio_gain: f32,
+            error: *mut root::RSnowboyError,
+        );
     }
     extern "C" {
-        pub fn detect_update_model(detector: *mut root::RSnowboyDetect);
+        pub fn detect_update_model(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        );
     }
     extern "C" {
-        pub fn detect_num_hotwords(detector: *mut root::RSnowboyDetect) -> ::std::os::raw::c_int;
+        pub fn detect_num_hotwords(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn detect_apply_frontend(detector: *mut root::RSnowboyDetect, apply_frontend: bool);
+        pub fn detect_apply_frontend(
+            detector: *mut root::RSnowboyDetect,
+            apply_frontend: bool,
+            error: *mut root::RSnowboyError,
+        );
     }
     extern "C" {
-        pub fn detect_sample_rate(detector: *mut root::RSnowboyDetect) -> ::std::os::raw::c_int;
+        pub fn detect_sample_rate(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn detect_num_channels(detector: *mut root::RSnowboyDetect) -> ::std::os::raw::c_int;
+        pub fn detect_num_channels(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn detect_bits_per_sample(detector: *mut root::RSnowboyDetect)
-            -> ::std::os::raw::c_int;
+        pub fn detect_bits_per_sample(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn detect_destroy(detector: *mut root::RSnowboyDetect);
+        pub fn detect_destroy(
+            detector: *mut root::RSnowboyDetect,
+            error: *mut root::RSnowboyError,
+        );
     }
     extern "C" {
         pub fn vad_create(
             resource_filename: *const ::std::os::raw::c_char,
+            error: *mut root::RSnowboyError,
         ) -> *mut root::RSnowboyVad;
     }
     extern "C" {
-        pub fn vad_reset(vad: *mut root::RSnowboyVad) -> bool;
+        pub fn vad_reset(
+            vad: *mut root::RSnowboyVad,
+            error: *mut root::RSnowboyError,
+        ) -> bool;
     }
     extern "C" {
-        pub fn vad_run_char(
+        pub fn vad_run_bytes(
             vad: *mut root::RSnowboyVad,
             data: *const ::std::os::raw::c_char,
+            length: usize,
+            is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
@@ -279,6 +342,7 @@ pub mod root {
             data: *const f32,
             array_length: ::std::os::raw::c_int,
             is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
@@ -287,6 +351,7 @@ pub mod root {
             data: *const i16,
             array_length: ::std::os::raw::c_int,
             is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
@@ -295,24 +360,45 @@ pub mod root {
             data: *const i32,
             array_length: ::std::os::raw::c_int,
             is_end: bool,
+            error: *mut root::RSnowboyError,
         ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn vad_set_audio_gain(vad: *mut root::RSnowboyVad, audio_gain: f32);
+        pub fn vad_set_audio_gain(
+            vad: *mut root::RSnowboyVad,
+            audio_gain: f32,
+            error: *mut root::RSnowboyError,
+        );
     }
     extern "C" {
-        pub fn vad_apply_frontend(vad: *mut root::RSnowboyVad, apply_frontend: bool);
+        pub fn vad_apply_frontend(
+            vad: *mut root::RSnowboyVad,
+            apply_frontend: bool,
+            error: *mut root::RSnowboyError,
+        );
     }
     extern "C" {
-        pub fn vad_sample_rate(vad: *mut root::RSnowboyVad) -> ::std::os::raw::c_int;
+        pub fn vad_sample_rate(
+            vad: *mut root::RSnowboyVad,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn vad_num_channels(vad: *mut root::RSnowboyVad) -> ::std::os::raw::c_int;
+        pub fn vad_num_channels(
+            vad: *mut root::RSnowboyVad,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn vad_bits_per_sample(vad: *mut root::RSnowboyVad) -> ::std::os::raw::c_int;
+        pub fn vad_bits_per_sample(
+            vad: *mut root::RSnowboyVad,
+            error: *mut root::RSnowboyError,
+        ) -> ::std::os::raw::c_int;
     }
     extern "C" {
-        pub fn vad_destroy(vad: *mut root::RSnowboyVad);
+        pub fn vad_destroy(
+            vad: *mut root::RSnowboyVad,
+            error: *mut root::RSnowboyError,
+        );
     }
 }
@@ -0,0 +1,304 @@
+//! Sample rate conversion.
+//!
+//! snowboy only takes audio at the rate returned by `SnowboyDetect::sample_rate`,
+//! 16 kHz for the bundled resources. `Resampler` converts audio captured at
+//! another rate, e.g. 44.1 or 48 kHz, as it arrives.
+//!
+//! The conversion is a windowed-sinc interpolation, computed as a polyphase
+//! filter: the output rate divided by the input rate is reduced to a fraction
+//! L/M, and each output sample uses one of L precomputed filter phases. The
+//! filter is a low-pass below the lower of the two Nyquist frequencies, so
+//! downsampling does not fold higher frequencies into the speech band.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rsnowboy::resample::{Quality, Resampler};
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let mut resampler = Resampler::for_format(48000, detector.format(), Quality::High)?;
+//! let captured: Vec<i16> = vec![0; 4800];
+//! let mut samples = Vec::new();
+//! resampler.process_i16(&captured, &mut samples);
+//! let result = detector.detect(&samples, false)?;
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use crate::error::SnowboyError;
+use crate::format::AudioFormat;
+
+/// More phases than this are not precomputed, the phase of each output sample
+/// is rounded to one of this many instead.
+const MAX_PHASES: u64 = 1024;
+
+/// Trade-off between the cost of the conversion and its accuracy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quality {
+  /// Short filter, with a wide transition band and about 50 dB of stop band
+  /// attenuation.
+  Fast,
+  /// About 70 dB of stop band attenuation.
+  Medium,
+  /// Long filter, with a narrow transition band and about 90 dB of stop band
+  /// attenuation.
+  High,
+}
+
+impl Quality {
+  /// Returns the number of zero crossings of the sinc on each side, the cut
+  /// off as a fraction of the Nyquist frequency, and the beta of the Kaiser
+  /// window.
+  fn parameters(self) -> (f64, f64, f64) {
+    match self {
+      Quality::Fast => (8.0, 0.85, 5.0),
+      Quality::Medium => (16.0, 0.90, 7.0),
+      Quality::High => (32.0, 0.94, 9.0),
+    }
+  }
+}
+
+/// Converts a stream of interleaved samples from one sample rate to another.
+///
+/// The resampler keeps the end of each chunk to compute the start of the next
+/// one, so a stream split into chunks of any size is converted exactly as if it
+/// was processed at once. `flush` returns the end of the stream, which is held
+/// back until then.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+  input_rate: u32,
+  output_rate: u32,
+  channels: usize,
+  /// The output rate is `up / down` times the input rate.
+  up: u64,
+  down: u64,
+  phases: u64,
+  /// Number of taps of each phase, which is even.
+  taps: usize,
+  /// `phases` filters of `taps` coefficients.
+  filters: Vec<f32>,
+  /// Interleaved input frames that are still needed.
+  history: Vec<f32>,
+  /// Index in `history` of the frame at or before the next output sample.
+  position: usize,
+  /// Position of the next output sample between `position` and the next
+  /// frame, in units of 1/`up` frame.
+  phase: u64,
+  frames_in: u64,
+  frames_out: u64,
+}
+
+impl Resampler {
+  /// Creates a resampler for `channels` interleaved channels.
+  pub fn new(input_rate: u32, output_rate: u32, channels: usize, quality: Quality) -> Result<Self, SnowboyError> {
+    if input_rate == 0 || output_rate == 0 || channels == 0 {
+      return Err(SnowboyError::InvalidArgument(format!("can not resample {} channels from {} Hz to {} Hz",
+                                                       channels, input_rate, output_rate)));
+    }
+    let divisor = gcd(u64::from(input_rate), u64::from(output_rate));
+    let up = u64::from(output_rate) / divisor;
+    let down = u64::from(input_rate) / divisor;
+    let phases = up.min(MAX_PHASES);
+
+    let (zero_crossings, rolloff, beta) = quality.parameters();
+    // Below the lower Nyquist frequency, in units of the input Nyquist.
+    let cutoff = (up as f64 / down as f64).min(1.0) * rolloff;
+    let half = (zero_crossings / cutoff).ceil() as usize;
+    let taps = 2 * half;
+    let mut filters = Vec::with_capacity(phases as usize * taps);
+    for phase in 0..phases {
+      let fraction = phase as f64 / phases as f64;
+      let start = filters.len();
+      for tap in 0..taps {
+        // Distance from the output sample to input frame `position + offset`.
+        let offset = tap as f64 - half as f64 + 1.0;
+        let t = offset - fraction;
+        filters.push((cutoff * sinc(cutoff * t) * kaiser(t / half as f64, beta)) as f32);
+      }
+      // Keep the gain at exactly 1 for a constant signal, whatever the phase.
+      let sum: f32 = filters[start..].iter().sum();
+      for coefficient in &mut filters[start..] {
+        *coefficient /= sum;
+      }
+    }
+
+    let mut resampler = Self {
+      input_rate,
+      output_rate,
+      channels,
+      up,
+      down,
+      phases,
+      taps,
+      filters,
+      history: Vec::new(),
+      position: 0,
+      phase: 0,
+      frames_in: 0,
+      frames_out: 0,
+    };
+    resampler.reset();
+    Ok(resampler)
+  }
+
+  /// Creates a resampler from `input_rate` to the rate and number of channels
+  /// of `format`, e.g. `SnowboyDetect::format`.
+  pub fn for_format(input_rate: u32, format: AudioFormat, quality: Quality) -> Result<Self, SnowboyError> {
+    Self::new(input_rate, format.sample_rate, format.num_channels as usize, quality)
+  }
+
+  pub fn input_rate(&self) -> u32 {
+    self.input_rate
+  }
+
+  pub fn output_rate(&self) -> u32 {
+    self.output_rate
+  }
+
+  pub fn channels(&self) -> usize {
+    self.channels
+  }
+
+  /// Returns the number of input frames an output sample waits for, before it
+  /// can be computed.
+  pub fn latency(&self) -> usize {
+    self.taps / 2
+  }
+
+  /// Forgets the stream, to start converting a new one.
+  pub fn reset(&mut self) {
+    let half = self.taps / 2;
+    // The frames before the start of the stream are silence.
+    self.history.clear();
+    self.history.resize((half - 1) * self.channels, 0.0);
+    self.position = half - 1;
+    self.phase = 0;
+    self.frames_in = 0;
+    self.frames_out = 0;
+  }
+
+  /// Converts `input`, interleaved samples that must be whole frames, and
+  /// appends the samples that can be computed so far to `output`.
+  pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+    debug_assert!(input.len().is_multiple_of(self.channels), "input is not a whole number of frames");
+    self.history.extend_from_slice(input);
+    self.frames_in += (input.len() / self.channels) as u64;
+    self.run(output, u64::MAX);
+  }
+
+  /// Returns the end of the stream, and resets the resampler.
+  pub fn flush(&mut self, output: &mut Vec<f32>) {
+    // The frames after the end of the stream are silence too.
+    let half = self.taps / 2;
+    self.history.resize(self.history.len() + (half + 1) * self.channels, 0.0);
+    let expected = (self.frames_in * self.up).div_ceil(self.down);
+    self.run(output, expected);
+    self.reset();
+  }
+
+  /// Same as `process`, for 16-bits samples. The output is rounded and
+  /// clipped.
+  pub fn process_i16(&mut self, input: &[i16], output: &mut Vec<i16>) {
+    let input = input.iter().map(|&sample| f32::from(sample)).collect::<Vec<f32>>();
+    let mut converted = Vec::new();
+    self.process(&input, &mut converted);
+    output.extend(converted.into_iter().map(to_i16));
+  }
+
+  /// Same as `flush`, for 16-bits samples.
+  pub fn flush_i16(&mut self, output: &mut Vec<i16>) {
+    let mut converted = Vec::new();
+    self.flush(&mut converted);
+    output.extend(converted.into_iter().map(to_i16));
+  }
+
+  /// Computes output samples while the frames they need are there, and until
+  /// `limit` samples were returned since the start of the stream.
+  fn run(&mut self, output: &mut Vec<f32>, limit: u64) {
+    let half = self.taps / 2;
+    let channels = self.channels;
+    let frames = self.history.len() / channels;
+    while self.frames_out < limit {
+      let (position, phase) = self.filter_phase();
+      if position + half >= frames {
+        break;
+      }
+      let filter = &self.filters[phase as usize * self.taps..(phase as usize + 1) * self.taps];
+      let first = (position + 1 - half) * channels;
+      for channel in 0..channels {
+        let mut sum = 0.0;
+        for (tap, coefficient) in filter.iter().enumerate() {
+          sum += self.history[first + tap * channels + channel] * coefficient;
+        }
+        output.push(sum);
+      }
+      self.frames_out += 1;
+      self.phase += self.down;
+      self.position += (self.phase / self.up) as usize;
+      self.phase %= self.up;
+    }
+
+    // Drop the frames no output sample needs anymore.
+    let keep_from = (self.position + 1).saturating_sub(half).min(frames);
+    self.history.drain(..keep_from * channels);
+    self.position -= keep_from;
+  }
+
+  /// Returns the frame and the filter phase of the next output sample.
+  fn filter_phase(&self) -> (usize, u64) {
+    if self.phases == self.up {
+      return (self.position, self.phase);
+    }
+    let rounded = (self.phase * self.phases + self.up / 2) / self.up;
+    if rounded == self.phases {
+      // Rounded up to the next frame.
+      (self.position + 1, 0)
+    } else {
+      (self.position, rounded)
+    }
+  }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+  while b != 0 {
+    let rest = a % b;
+    a = b;
+    b = rest;
+  }
+  a
+}
+
+fn sinc(x: f64) -> f64 {
+  if x == 0.0 {
+    1.0
+  } else {
+    let x = x * std::f64::consts::PI;
+    x.sin() / x
+  }
+}
+
+/// Kaiser window, for `x` between -1 and 1.
+fn kaiser(x: f64, beta: f64) -> f64 {
+  if x.abs() > 1.0 {
+    return 0.0;
+  }
+  bessel_i0(beta * (1.0 - x * x).sqrt()) / bessel_i0(beta)
+}
+
+/// Zeroth order modified Bessel function of the first kind.
+fn bessel_i0(x: f64) -> f64 {
+  let mut sum = 1.0;
+  let mut term = 1.0;
+  let half = x / 2.0;
+  let mut k = 1.0;
+  while term > sum * 1e-12 {
+    term *= (half / k) * (half / k);
+    sum += term;
+    k += 1.0;
+  }
+  sum
+}
+
+pub(crate) fn to_i16(sample: f32) -> i16 {
+  sample.round().max(f32::from(i16::MIN)).min(f32::from(i16::MAX)) as i16
+}
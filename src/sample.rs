@@ -0,0 +1,138 @@
+use std::convert::TryFrom;
+use std::os::raw::c_int;
+
+use crate::error::{self, SnowboyError};
+use crate::format::SampleFormat;
+use crate::rawrsnoboy::root as rsnowboy;
+
+/// Audio sample types accepted by `SnowboyDetect::detect` and
+/// `SnowboyVad::detect`.
+///
+/// Implemented for `u8` (8-bits unsigned PCM), `i16`, `i32` and `f32`. This
+/// trait is sealed and can not be implemented outside of this crate.
+pub trait Sample: private::Sealed + Copy {}
+
+impl Sample for u8 {}
+
+impl Sample for i16 {}
+
+impl Sample for i32 {}
+
+impl Sample for f32 {}
+
+pub(crate) mod private {
+  use std::os::raw::c_int;
+
+  use crate::error::SnowboyError;
+  use crate::format::SampleFormat;
+  use crate::rawrsnoboy::root as rsnowboy;
+
+  pub trait Sealed: Sized {
+    /// Encoding of the type in an audio stream.
+    const SAMPLE_FORMAT: SampleFormat;
+
+    const BITS_PER_SAMPLE: u16;
+
+    /// Appends `samples` to `bytes`, in little endian.
+    fn extend_le(samples: &[Self], bytes: &mut Vec<u8>);
+
+    /// Returns the bits of the sample, which fit in 32 bits, e.g. to keep it
+    /// in an `AtomicU32`.
+    fn to_bits(self) -> u32;
+
+    fn from_bits(bits: u32) -> Self;
+
+    /// Runs the detector over `data`, which has already been checked to hold
+    /// `length` samples.
+    unsafe fn run_detection(detector: *mut rsnowboy::RSnowboyDetect, data: &[Self],
+                            length: c_int, is_end: bool) -> Result<c_int, SnowboyError>;
+
+    unsafe fn run_vad(vad: *mut rsnowboy::RSnowboyVad, data: &[Self],
+                      length: c_int, is_end: bool) -> Result<c_int, SnowboyError>;
+  }
+}
+
+macro_rules! native_sample {
+  ($ty:ty, $sample_format:expr, $bits:expr, $detect:ident, $vad:ident) => {
+    impl private::Sealed for $ty {
+      const SAMPLE_FORMAT: SampleFormat = $sample_format;
+
+      const BITS_PER_SAMPLE: u16 = $bits;
+
+      fn extend_le(samples: &[Self], bytes: &mut Vec<u8>) {
+        for sample in samples {
+          bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+      }
+
+      fn to_bits(self) -> u32 {
+        let mut bits = [0; 4];
+        bits[..std::mem::size_of::<$ty>()].copy_from_slice(&self.to_le_bytes());
+        u32::from_le_bytes(bits)
+      }
+
+      fn from_bits(bits: u32) -> Self {
+        let mut bytes = [0; std::mem::size_of::<$ty>()];
+        bytes.copy_from_slice(&bits.to_le_bytes()[..std::mem::size_of::<$ty>()]);
+        <$ty>::from_le_bytes(bytes)
+      }
+
+      unsafe fn run_detection(detector: *mut rsnowboy::RSnowboyDetect, data: &[Self],
+                              length: c_int, is_end: bool) -> Result<c_int, SnowboyError> {
+        error::catch(|error| rsnowboy::$detect(detector, data.as_ptr(), length, is_end, error))
+      }
+
+      unsafe fn run_vad(vad: *mut rsnowboy::RSnowboyVad, data: &[Self],
+                        length: c_int, is_end: bool) -> Result<c_int, SnowboyError> {
+        error::catch(|error| rsnowboy::$vad(vad, data.as_ptr(), length, is_end, error))
+      }
+    }
+  };
+}
+
+native_sample!(i16, SampleFormat::Int, 16, detect_run_short_array_detection, vad_run_short_array);
+native_sample!(i32, SampleFormat::Int, 32, detect_run_integer_array_detection, vad_run_integer_array);
+native_sample!(f32, SampleFormat::Float, 32, detect_run_float_array_detection, vad_run_float_array);
+
+// snowboy has no array entry point for 8-bits PCM, so widen it to 16-bits.
+impl private::Sealed for u8 {
+  const SAMPLE_FORMAT: SampleFormat = SampleFormat::Int;
+
+  const BITS_PER_SAMPLE: u16 = 8;
+
+  fn extend_le(samples: &[Self], bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(samples);
+  }
+
+  fn to_bits(self) -> u32 {
+    u32::from(self)
+  }
+
+  fn from_bits(bits: u32) -> Self {
+    bits as u8
+  }
+
+  unsafe fn run_detection(detector: *mut rsnowboy::RSnowboyDetect, data: &[Self],
+                          length: c_int, is_end: bool) -> Result<c_int, SnowboyError> {
+    <i16 as private::Sealed>::run_detection(detector, &widen_u8(data), length, is_end)
+  }
+
+  unsafe fn run_vad(vad: *mut rsnowboy::RSnowboyVad, data: &[Self],
+                    length: c_int, is_end: bool) -> Result<c_int, SnowboyError> {
+    <i16 as private::Sealed>::run_vad(vad, &widen_u8(data), length, is_end)
+  }
+}
+
+fn widen_u8(data: &[u8]) -> Vec<i16> {
+  data.iter().map(|&sample| (i16::from(sample) - 128) << 8).collect()
+}
+
+/// Checks that `samples` is a whole number of frames for `num_channels`, and
+/// that it fits in the length argument of the native functions.
+pub(crate) fn array_length(samples: usize, num_channels: i32) -> Result<c_int, SnowboyError> {
+  let channels = usize::try_from(num_channels).unwrap_or(0).max(1);
+  if !samples.is_multiple_of(channels) {
+    return Err(SnowboyError::PartialFrame { samples, channels });
+  }
+  c_int::try_from(samples).map_err(|_| SnowboyError::BufferTooLarge { samples })
+}
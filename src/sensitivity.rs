@@ -0,0 +1,139 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+
+use crate::error::SnowboyError;
+
+/// Detection sensitivities, one value between 0 and 1 per hotword, in the same
+/// order as the hotword indices. A higher sensitivity gives more detections,
+/// but also more false alarms.
+///
+/// # Examples
+///
+/// ```
+/// # use rsnowboy::Sensitivities;
+/// use std::num::NonZeroU32;
+///
+/// let mut sensitivities = Sensitivities::new(vec![0.4, 0.5])?;
+/// sensitivities.set_hotword(NonZeroU32::new(2).unwrap(), 0.8)?;
+/// assert_eq!(sensitivities.to_string(), "0.4,0.8");
+/// assert!(Sensitivities::new(vec![1.5]).is_err());
+/// # Ok::<(), rsnowboy::SnowboyError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensitivities(Vec<f32>);
+
+impl Sensitivities {
+  /// Creates sensitivities from a list of values, each between 0 and 1.
+  pub fn new<V>(values: V) -> Result<Self, SnowboyError> where V: Into<Vec<f32>> {
+    let values = values.into();
+    for &value in &values {
+      check_value(value)?;
+    }
+    Ok(Sensitivities(values))
+  }
+
+  /// Creates `count` sensitivities that all have the same value.
+  pub fn uniform(value: f32, count: usize) -> Result<Self, SnowboyError> {
+    Sensitivities::new(vec![value; count])
+  }
+
+  /// Returns the number of values, which is the number of hotwords they are
+  /// meant for.
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn as_slice(&self) -> &[f32] {
+    &self.0
+  }
+
+  /// Returns the sensitivity of hotword `index`. Indices start at 1, as in
+  /// `DetectionResult::Hotword`.
+  pub fn hotword(&self, index: NonZeroU32) -> Option<f32> {
+    self.0.get(index.get() as usize - 1).copied()
+  }
+
+  /// Sets the sensitivity of hotword `index`. Indices start at 1, as in
+  /// `DetectionResult::Hotword`.
+  pub fn set_hotword(&mut self, index: NonZeroU32, value: f32) -> Result<(), SnowboyError> {
+    check_value(value)?;
+    let num_hotwords = self.0.len();
+    match self.0.get_mut(index.get() as usize - 1) {
+      Some(slot) => {
+        *slot = value;
+        Ok(())
+      }
+      None => Err(SnowboyError::HotwordIndex { index, num_hotwords }),
+    }
+  }
+}
+
+fn check_value(value: f32) -> Result<(), SnowboyError> {
+  if (0.0..=1.0).contains(&value) {
+    Ok(())
+  } else {
+    Err(SnowboyError::InvalidSensitivity(value))
+  }
+}
+
+/// Formats the values as the comma separated string snowboy expects.
+impl fmt::Display for Sensitivities {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, value) in self.0.iter().enumerate() {
+      if i > 0 {
+        write!(f, ",")?;
+      }
+      write!(f, "{}", value)?;
+    }
+    Ok(())
+  }
+}
+
+/// Parses a comma separated string such as "0.4,0.5,0.8".
+impl FromStr for Sensitivities {
+  type Err = SnowboyError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let values = s.split(',')
+      .map(|value| value.trim().parse::<f32>()
+        .map_err(|_| SnowboyError::InvalidArgument(format!("sensitivity {:?} is not a number", value))))
+      .collect::<Result<Vec<f32>, _>>()?;
+    Sensitivities::new(values)
+  }
+}
+
+impl TryFrom<Vec<f32>> for Sensitivities {
+  type Error = SnowboyError;
+
+  fn try_from(values: Vec<f32>) -> Result<Self, Self::Error> {
+    Sensitivities::new(values)
+  }
+}
+
+impl TryFrom<&[f32]> for Sensitivities {
+  type Error = SnowboyError;
+
+  fn try_from(values: &[f32]) -> Result<Self, Self::Error> {
+    Sensitivities::new(values)
+  }
+}
+
+impl TryFrom<&Sensitivities> for Sensitivities {
+  type Error = SnowboyError;
+
+  fn try_from(sensitivities: &Sensitivities) -> Result<Self, Self::Error> {
+    Ok(sensitivities.clone())
+  }
+}
+
+impl From<Sensitivities> for Vec<f32> {
+  fn from(sensitivities: Sensitivities) -> Self {
+    sensitivities.0
+  }
+}
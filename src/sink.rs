@@ -0,0 +1,205 @@
+//! Hotword detection as an `io::Write`.
+//!
+//! `DetectorSink` is the Rust side of the Node binding's writable stream:
+//! raw little-endian PCM written to it is split into chunks of 100 ms, and the
+//! result of every chunk is sent as a `DetectionEvent` to a callback or a
+//! channel. Writes can have any length, and may split a sample or a frame.
+//!
+//! As the sink is an `io::Write`, any `Read` can be piped into it with
+//! `io::copy`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io;
+//! use std::sync::mpsc;
+//!
+//! use rsnowboy::events::EventKind;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let (sender, receiver) = mpsc::channel();
+//! let mut sink = detector.sink_channel(sender)?;
+//! io::copy(&mut io::stdin(), &mut sink)?;
+//! sink.finish()?;
+//! drop(sink);
+//!
+//! for event in receiver.try_iter() {
+//!   match event {
+//!     Ok(event) if event.kind == EventKind::Hotword => println!("Hotword at {:?}", event.timestamp),
+//!     Ok(_) => {}
+//!     Err(error) => eprintln!("Detection failed: {}", error),
+//!   }
+//! }
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::mpsc::Sender;
+
+use crate::chunker::Chunker;
+use crate::detection::DetectionResult;
+use crate::error::SnowboyError;
+use crate::events::DetectionEvent;
+use crate::snowboy::SnowboyDetect;
+use crate::source::CHUNK_MILLIS;
+use crate::wav::Detection;
+
+/// Runs hotword detection over the bytes written to it, see `SnowboyDetect::sink`.
+///
+/// Every chunk gives a `Silence`, `Sound` or `Hotword` event. When detection
+/// fails, the events of the chunks before it are sent, and the write stops
+/// before the chunk that failed: it returns the number of bytes up to there,
+/// or, if there are none, sends the error as an event and returns an
+/// `io::Error` with its message.
+///
+/// The last chunk is only detected by `finish`, which should be called once
+/// the stream ends. `flush` does not end the stream.
+pub struct DetectorSink<'a> {
+  detector: &'a mut SnowboyDetect,
+  chunker: Chunker<u8>,
+  on_event: Box<dyn FnMut(Result<DetectionEvent, SnowboyError>) + 'a>,
+}
+
+impl<'a> DetectorSink<'a> {
+  /// Creates a sink that calls `on_event` with every event.
+  pub fn new<F>(detector: &'a mut SnowboyDetect, on_event: F) -> Result<Self, SnowboyError>
+    where F: FnMut(Result<DetectionEvent, SnowboyError>) + 'a {
+    let chunker = Chunker::for_format(CHUNK_MILLIS, detector.format())?;
+    Ok(Self { detector, chunker, on_event: Box::new(on_event) })
+  }
+
+  /// Creates a sink that sends every event to `sender`. Events are dropped
+  /// once the receiver is gone.
+  pub fn with_channel(detector: &'a mut SnowboyDetect,
+                      sender: Sender<Result<DetectionEvent, SnowboyError>>) -> Result<Self, SnowboyError> {
+    Self::new(detector, move |event| {
+      let _ = sender.send(event);
+    })
+  }
+
+  /// Returns the offset of the next chunk, in samples per channel from the
+  /// start of the stream.
+  pub fn sample_offset(&self) -> u64 {
+    self.chunker.sample_offset()
+  }
+
+  /// Ends the stream: runs detection over the bytes that are left, and sends
+  /// the last event. Bytes written afterwards start a new stream.
+  ///
+  /// When the stream ends on a chunk boundary, there is only an event if
+  /// snowboy reports a hotword at the end, as with `SnowboyDetect::events`.
+  /// An error is returned if the stream ends with part of a frame.
+  pub fn finish(&mut self) -> io::Result<()> {
+    let detector = &mut *self.detector;
+    let is_empty = self.chunker.buffered() == 0;
+    match self.chunker.finish(|chunk, is_end| detect(detector, chunk, is_end)) {
+      Ok(detection) if is_empty && detection.result.hotword().is_none() => Ok(()),
+      Ok(detection) => {
+        self.send(vec![detection]);
+        Ok(())
+      }
+      Err(error) => Err(self.fail(error)),
+    }
+  }
+
+  /// Sends the events of `detections`.
+  fn send(&mut self, detections: Vec<Detection<DetectionResult>>) {
+    let sample_rate = self.detector.format().sample_rate;
+    for detection in detections {
+      (self.on_event)(Ok(DetectionEvent::from_result(&detection.result, detection.sample_offset, sample_rate)));
+    }
+  }
+
+  /// Sends `error`, and returns it as an `io::Error`.
+  fn fail(&mut self, error: SnowboyError) -> io::Error {
+    let message = error.to_string();
+    (self.on_event)(Err(error));
+    io::Error::other(message)
+  }
+}
+
+impl fmt::Debug for DetectorSink<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("DetectorSink")
+      .field("detector", &self.detector)
+      .field("chunker", &self.chunker)
+      .finish()
+  }
+}
+
+impl Write for DetectorSink<'_> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let detector = &mut *self.detector;
+    match self.chunker.push(buf, |chunk, is_end| detect(detector, chunk, is_end)) {
+      Ok(detections) => {
+        self.send(detections);
+        Ok(buf.len())
+      }
+      Err(error) => {
+        self.send(error.detections);
+        // The next write starts with the chunk that failed, and returns the
+        // error if it fails again.
+        if error.consumed > 0 {
+          return Ok(error.consumed);
+        }
+        Err(self.fail(error.error))
+      }
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+impl SnowboyDetect {
+  /// Returns an `io::Write` that runs hotword detection over the raw
+  /// little-endian PCM written to it, in the format of the detector, and
+  /// calls `on_event` with the result of every chunk, see `DetectorSink`.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  /// use std::fs::File;
+  /// use std::io;
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// let mut hotwords = 0;
+  /// let mut sink = detector.sink(|event| {
+  ///   if let Ok(event) = event {
+  ///     hotwords += event.hotword_index.is_some() as u32;
+  ///   }
+  /// })?;
+  /// io::copy(&mut File::open("recording.raw")?, &mut sink)?;
+  /// sink.finish()?;
+  /// drop(sink);
+  /// println!("{} hotwords", hotwords);
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn sink<'a, F>(&'a mut self, on_event: F) -> Result<DetectorSink<'a>, SnowboyError>
+    where F: FnMut(Result<DetectionEvent, SnowboyError>) + 'a {
+    DetectorSink::new(self, on_event)
+  }
+
+  /// Returns an `io::Write` that sends the result of every chunk to `sender`,
+  /// see `sink`.
+  pub fn sink_channel(&mut self, sender: Sender<Result<DetectionEvent, SnowboyError>>)
+                      -> Result<DetectorSink<'_>, SnowboyError> {
+    DetectorSink::with_channel(self, sender)
+  }
+}
+
+/// Runs detection over `chunk`, little-endian bytes, whatever the byte order
+/// of the machine.
+fn detect(detector: &mut SnowboyDetect, chunk: &[u8], is_end: bool) -> Result<DetectionResult, SnowboyError> {
+  if cfg!(target_endian = "big") {
+    let sample_len = (detector.format().bits_per_sample as usize).div_ceil(8).max(1);
+    let mut native = chunk.to_vec();
+    native.chunks_exact_mut(sample_len).for_each(|sample| sample.reverse());
+    return detector.detect_bytes(&native, is_end);
+  }
+  detector.detect_bytes(chunk, is_end)
+}
@@ -1,55 +1,206 @@
-use std::ffi::{CString, CStr};
-
+use std::convert::TryInto;
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::num::NonZeroU32;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+
+use crate::builder::DetectorBuilder;
+use crate::convert::{AudioBuffer, Converter};
+use crate::detection::{DetectionResult, VadResult};
+use crate::error::{self, SnowboyError};
+use crate::events::Events;
+use crate::format::AudioFormat;
+use crate::memory::MemoryFile;
+use crate::models::HotwordModels;
 use crate::rawrsnoboy::root as rsnowboy;
+use crate::sample::{self, Sample};
+use crate::sensitivity::Sensitivities;
+use crate::source::{self, AudioSource};
+use crate::wav::{Detection, WavReader};
 
 /// SnowboyDetect class interface.
-#[derive(Debug, Copy, Clone)]
+///
+/// The detector owns the underlying native object, which is released when the
+/// detector is dropped.
+#[derive(Debug)]
 pub struct SnowboyDetect {
-  rsnowboy_detect: *mut rsnowboy::RSnowboyDetect
+  rsnowboy_detect: *mut rsnowboy::RSnowboyDetect,
+  num_hotwords: i32,
+  sample_rate: i32,
+  num_channels: i32,
+  bits_per_sample: i32,
+  high_sensitivity: Option<Sensitivities>,
+  models: Option<HotwordModels>,
+  converter: Option<Converter>,
+  // Files written by `from_bytes`, kept until the native detector is released.
+  _memory_files: Vec<MemoryFile>,
 }
 
+// The native detector has no thread affinity, so it can be moved to another
+// thread. Everything that changes its state or writes files takes `&mut self`,
+// and the `&self` methods only call const functions, so shared references are
+// safe too.
 unsafe impl Send for SnowboyDetect {}
 
 unsafe impl Sync for SnowboyDetect {}
 
 impl SnowboyDetect {
-  /// Constructor that takes a resource file, and a list of hotword models which
-  /// are separated by comma. In the case that more than one hotword exist in the
+  /// Constructor that takes a resource file, and a list of hotword models. In
+  /// the case that more than one hotword exist in the
   /// provided models, RunDetection() will return the index of the hotword, if
   /// the corresponding hotword is triggered.
   ///
   /// CAVEAT: a personal model only contain one hotword, but an universal model
   ///         may contain multiple hotwords. It is your responsibility to figure
-  ///         out the index of the hotword. For example, if your model string is
-  ///         "foo.pmdl,bar.umdl", where foo.pmdl contains hotword x, bar.umdl
+  ///         out the index of the hotword. For example, if your models are
+  ///         ["foo.pmdl", "bar.umdl"], where foo.pmdl contains hotword x, bar.umdl
   ///         has two hotwords y and z, the indices of different hotwords are as
   ///         follows:
   ///         x 1
   ///         y 2
   ///         z 3
   ///
+  /// `with_models` keeps track of the indices, and the hotword names, for you.
+  ///
+  /// Relative paths are resolved against the current directory when the
+  /// detector is created. snowboy separates model files by comma, so a path
+  /// that contains a comma, or a NUL byte, is rejected. The resource file and
+  /// every model file are checked before they are handed to snowboy, and the
+  /// error names the file that is missing, can not be read or is not a snowboy
+  /// model. Only the header of a file is checked: a file that starts like a
+  /// model but is corrupt further on still reaches snowboy, which may abort
+  /// the process on it.
+  ///
   /// @param [in]  resource_filename   Filename of resource file.
-  /// @param [in]  model_str           A string of multiple hotword models,
-  ///                                  separated by comma.
+  /// @param [in]  model_filenames     Filenames of the hotword models.
   ///
   /// # Examples
   ///
   /// ```no_run
   /// # use rsnowboy::SnowboyDetect;
   ///
-  /// let _ = SnowboyDetect::new("resources/common.res", "resources/models/snowboy.umdl");
+  /// let detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
   /// ```
   ///
-  pub fn new<S>(resource_filename: S, model_str: S) -> Self where S: AsRef<str> {
+  pub fn new<R, I>(resource_filename: R, model_filenames: I) -> Result<Self, SnowboyError>
+    where R: AsRef<Path>, I: IntoIterator, I::Item: AsRef<Path> {
+    let resource_filename = resolve_path(resource_filename.as_ref())?;
+    let model_filenames = model_filenames.into_iter()
+      .map(|model| resolve_path(model.as_ref()))
+      .collect::<Result<Vec<PathBuf>, _>>()?;
+    Self::create(&resource_filename, &model_filenames, Vec::new())
+  }
+
+  /// Constructor that takes the content of a resource file and of hotword
+  /// model files, e.g. embedded with `include_bytes!`. snowboy only loads
+  /// files, so the bytes are written to anonymous in-memory files where the
+  /// system has them, or to temporary files only the current user can read
+  /// otherwise. They are removed when the detector is dropped. The bytes are
+  /// checked as the files of `new` are, only their header.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  ///
+  /// let resource = std::fs::read("resources/common.res").unwrap();
+  /// let model = std::fs::read("resources/models/snowboy.umdl").unwrap();
+  /// let detector = SnowboyDetect::from_bytes(&resource, &[&model])?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn from_bytes(resource: &[u8], models: &[&[u8]]) -> Result<Self, SnowboyError> {
+    // Check the bytes first, so errors name the argument rather than the file.
+    check_resource_header(Path::new("<resource>"), resource)?;
+    let mut memory_files = vec![MemoryFile::new("resource", resource)?];
+    for (i, model) in models.iter().enumerate() {
+      let name = format!("model-{}", i + 1);
+      check_model_header(Path::new(&format!("<{}>", name)), model)?;
+      memory_files.push(MemoryFile::new(&name, model)?);
+    }
+    let filenames = memory_files.iter().map(|file| file.path().to_path_buf()).collect::<Vec<_>>();
+    Self::create(&filenames[0], &filenames[1..], memory_files)
+  }
+
+  /// Constructor that loads the resource and model bundled with the crate,
+  /// resources/common.res and resources/models/snowboy.umdl, from the binary.
+  #[cfg(feature = "embedded-resources")]
+  pub fn embedded() -> Result<Self, SnowboyError> {
+    Self::from_bytes(crate::embedded::COMMON_RES, &[crate::embedded::SNOWBOY_UMDL])
+  }
+
+  fn create(resource_filename: &Path, model_filenames: &[PathBuf],
+            memory_files: Vec<MemoryFile>) -> Result<Self, SnowboyError> {
+    if model_filenames.is_empty() {
+      return Err(SnowboyError::InvalidArgument("no hotword model was given".to_string()));
+    }
+
+    check_path_bytes(resource_filename)?;
+    check_resource(resource_filename)?;
+    for model in model_filenames {
+      check_path_bytes(model)?;
+      check_model(model)?;
+    }
+    let resource = path_to_cstring(resource_filename)?;
+    let model_str = model_filenames.iter()
+      .map(|model| path_bytes(model))
+      .collect::<Result<Vec<_>, _>>()?
+      .join(&b',');
+    let model_cstr = CString::new(model_str).expect("paths are checked for NUL bytes");
+
+    let rsnowboy_detect = unsafe {
+      error::catch(|error| rsnowboy::detect_create(resource.as_ptr(), model_cstr.as_ptr(), error))?
+    };
+    let mut detector = Self {
+      rsnowboy_detect,
+      num_hotwords: 0,
+      sample_rate: 0,
+      num_channels: 0,
+      bits_per_sample: 0,
+      high_sensitivity: None,
+      models: None,
+      converter: None,
+      _memory_files: memory_files,
+    };
     unsafe {
-      let rsnowboy_detect = rsnowboy::detect_create(
-        CString::new(resource_filename.as_ref()).unwrap().as_ptr(), //
-        CString::new(model_str.as_ref()).unwrap().as_ptr(),
-      );
-      Self {
-        rsnowboy_detect
-      }
+      detector.num_hotwords = error::catch(|error| rsnowboy::detect_num_hotwords(rsnowboy_detect, error))?;
+      detector.sample_rate = error::catch(|error| rsnowboy::detect_sample_rate(rsnowboy_detect, error))?;
+      detector.num_channels = error::catch(|error| rsnowboy::detect_num_channels(rsnowboy_detect, error))?;
+      detector.bits_per_sample = error::catch(|error| rsnowboy::detect_bits_per_sample(rsnowboy_detect, error))?;
+    }
+    Ok(detector)
+  }
+
+  /// Constructor that takes a resource file and the hotword models to load,
+  /// with the names of their hotwords. The sensitivities of the models are
+  /// applied, and `DetectionResult::Hotword` carries the name of the triggered
+  /// hotword.
+  ///
+  /// An error is returned if snowboy loads a different number of hotwords
+  /// from the models than they declare.
+  pub fn with_models<P>(resource_filename: P, models: HotwordModels) -> Result<Self, SnowboyError> where P: AsRef<Path> {
+    let mut detector = Self::new(resource_filename, models.files())?;
+    let declared = models.num_hotwords();
+    let loaded = detector.num_hotwords as usize;
+    if declared != loaded {
+      return Err(SnowboyError::HotwordCount { declared, loaded });
     }
+    detector.set_sensitivity(models.sensitivities())?;
+    detector.models = Some(models);
+    Ok(detector)
+  }
+
+  /// Returns the models the detector was created with by `with_models`.
+  pub fn models(&self) -> Option<&HotwordModels> {
+    self.models.as_ref()
+  }
+
+  /// Returns a `DetectorBuilder`, to set up a detector and its settings in one
+  /// go.
+  pub fn builder() -> DetectorBuilder {
+    DetectorBuilder::new()
   }
 
   /// Resets the detection. This class handles voice activity detection (VAD)
@@ -59,13 +210,14 @@ impl SnowboyDetect {
   /// ```no_run
   /// # use rsnowboy::SnowboyDetect;
   ///
-  /// let detector = SnowboyDetect::new("resources/common.res", "resources/models/snowboy.umdl");
-  /// detector.reset();
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// detector.reset()?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
   /// ```
   ///
-  pub fn reset(&self) -> bool {
+  pub fn reset(&mut self) -> Result<bool, SnowboyError> {
     unsafe {
-      rsnowboy::detect_reset(self.rsnowboy_detect)
+      error::catch(|error| rsnowboy::detect_reset(self.rsnowboy_detect, error))
     }
   }
 
@@ -77,106 +229,232 @@ impl SnowboyDetect {
   /// call RunDetection(). Larger chunk usually leads to longer delay, but less
   /// CPU usage.
   ///
-  /// Definition of return values:
-  /// -2: Silence.
-  /// -1: Error.
-  ///  0: No event.
-  ///  1: Hotword 1 triggered.
-  ///  2: Hotword 2 triggered.
-  ///  ...
+  /// The bytes are passed to snowboy as they are, together with their length,
+  /// so they may contain zero bytes. Returns the `DetectionResult` of the
+  /// chunk, or an error if snowboy reports one (-1).
   ///
   /// @param [in]  data               Small chunk of data to be detected. See
   ///                                 above for the supported data format.
   /// @param [in]  is_end             Set it to true if it is the end of a
   ///                                 utterance or file.
   ///
+  /// # Examples
   ///
-  pub fn run_char_detection(&self, data: *const ::std::os::raw::c_char, is_end: bool) -> i32 {
-    unsafe {
-      rsnowboy::detect_run_char_detection(self.rsnowboy_detect, data)
-    }
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  /// use std::io::Read;
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// let mut stream = std::net::TcpStream::connect("127.0.0.1:9000").unwrap();
+  /// let mut buffer = [0u8; 3200];
+  /// loop {
+  ///   let read = stream.read(&mut buffer).unwrap();
+  ///   let result = detector.detect_bytes(&buffer[..read], read == 0)?;
+  ///   if let Some(index) = result.hotword() {
+  ///     println!("Hotword {} detected!", index);
+  ///   }
+  ///   if read == 0 {
+  ///     break;
+  ///   }
+  /// }
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn detect_bytes(&mut self, data: &[u8], is_end: bool) -> Result<DetectionResult, SnowboyError> {
+    check_block_aligned(data, self.bits_per_sample, self.num_channels)?;
+    let code = unsafe {
+      error::catch(|error| rsnowboy::detect_run_bytes_detection(self.rsnowboy_detect, data.as_ptr() as *const c_char,
+                                                                data.len(), is_end, error))?
+    };
+    self.detection_result(code)
   }
 
-  /// Various versions of RunDetection() that take different format of audio. If
-  /// NumChannels() > 1, e.g., NumChannels() == 2, then the array is as follows:
+  /// Runs hotword detection over a slice of samples. This is the counterpart
+  /// of the various versions of RunDetection() that take different format of
+  /// audio, see `Sample` for the supported sample types. If NumChannels() > 1,
+  /// e.g., NumChannels() == 2, then the slice is as follows:
   ///
   ///   d1c1, d1c2, d2c1, d2c2, d3c1, d3c2, ..., dNc1, dNc2
   ///
   /// where d1c1 means data point 1 of channel 1.
   ///
-  /// @param [in]  data               Small chunk of data to be detected. See
+  /// Returns the `DetectionResult` of the chunk. An error is returned if
+  /// `samples` is not a whole number of frames, is too long to be passed to
+  /// snowboy in one call, or if snowboy reports an error (-1).
+  ///
+  /// @param [in]  samples            Small chunk of data to be detected. See
   ///                                 above for the supported data format.
-  /// @param [in]  array_length       Length of the data array.
   /// @param [in]  is_end             Set it to true if it is the end of a
   ///                                 utterance or file.
   ///
   /// # Examples
   ///
-  /// # Examples
-  ///
   /// ```no_run
   /// # use rsnowboy::SnowboyDetect;
   ///
-  /// let detector = SnowboyDetect::new("resources/common.res", "resources/models/snowboy.umdl");
-  /// detector.set_sensitivity("1");
-  /// detector.set_audio_gain(1 as f32);
-  /// detector.apply_frontend(false);
-  /// let voice = vec![
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// detector.set_sensitivity(vec![1.0])?;
+  /// detector.set_audio_gain(1 as f32)?;
+  /// detector.apply_frontend(false)?;
+  /// let voice: Vec<i16> = vec![
   ///   -11, -12, -10, -20, -15, -5, 2, 5, 8,
   ///   10, 12, 14, 13, 20, 25, 14, 12, 9, 12,
   ///   -1, -5, 20, 22, 24, 24, 14, 10, 15, 11
   /// ];
-  /// detector.run_short_array_detection(voice.as_ptr(), voice.len() as i32, false);
+  /// let result = detector.detect(&voice, false)?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
   /// ```
   ///
-  pub fn run_float_array_detection(&self, data: *const f32, array_length: ::std::os::raw::c_int, is_end: bool) -> i32 {
-    unsafe {
-      rsnowboy::detect_run_float_array_detection(self.rsnowboy_detect, data, array_length, is_end)
-    }
+  pub fn detect<S>(&mut self, samples: &[S], is_end: bool) -> Result<DetectionResult, SnowboyError> where S: Sample {
+    #[allow(deprecated)]
+    let code = self.detect_raw(samples, is_end)?;
+    self.detection_result(code)
   }
 
-  pub fn run_short_array_detection(&self, data: *const i16, array_length: ::std::os::raw::c_int, is_end: bool) -> i32 {
-    unsafe {
-      rsnowboy::detect_run_short_array_detection(self.rsnowboy_detect, data, array_length, is_end)
+  /// Runs hotword detection over samples of any encoding, converted to the
+  /// format of the detector first, see `convert`. The samples must be at the
+  /// sample rate of the detector, with the same number of channels.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  /// use rsnowboy::convert::{AudioBuffer, Encoding, Endian};
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// let captured = vec![0u8; 4800];
+  /// let buffer = AudioBuffer::Bytes { encoding: Encoding::I24, endian: Endian::Little, data: &captured };
+  /// let result = detector.detect_buffer(buffer, false)?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn detect_buffer(&mut self, buffer: AudioBuffer, is_end: bool) -> Result<DetectionResult, SnowboyError> {
+    let format = self.format();
+    let data = convert(&mut self.converter, format, buffer)?;
+    self.detect_bytes(&data, is_end)
+  }
+
+  /// Sets how `detect_buffer` converts samples, e.g. to dither them. The
+  /// converter must have the format of the detector.
+  pub fn set_converter(&mut self, converter: Converter) -> Result<(), SnowboyError> {
+    self.converter = Some(check_converter(converter, self.format())?);
+    Ok(())
+  }
+
+  /// Converts a RunDetection() code, naming the hotword when it is known.
+  fn detection_result(&self, code: i32) -> Result<DetectionResult, SnowboyError> {
+    match DetectionResult::from_code(code)? {
+      DetectionResult::Hotword { index, .. } => {
+        let name = self.models.as_ref()
+          .and_then(|models| models.lookup(index))
+          .map(str::to_string);
+        Ok(DetectionResult::Hotword { index, name })
+      }
+      result => Ok(result),
     }
   }
 
-  pub fn run_integer_array_detection(&self, data: *const i32, array_length: ::std::os::raw::c_int, is_end: bool) -> i32 {
+  /// Same as `detect`, but returns the raw RunDetection() code instead of a
+  /// `DetectionResult`.
+  #[deprecated(note = "use `detect`, which returns a `DetectionResult`")]
+  pub fn detect_raw<S>(&mut self, samples: &[S], is_end: bool) -> Result<i32, SnowboyError> where S: Sample {
+    let length = sample::array_length(samples.len(), self.num_channels())?;
     unsafe {
-      rsnowboy::detect_run_integer_array_detection(self.rsnowboy_detect, data, array_length, is_end)
+      S::run_detection(self.rsnowboy_detect, samples, length, is_end)
     }
   }
 
-  /// Sets the sensitivity string for the loaded hotwords. A <sensitivity_str> is
-  /// a list of floating numbers between 0 and 1, and separated by comma. For
-  /// example, if there are 3 loaded hotwords, your string should looks something
-  /// like this:
-  ///   0.4,0.5,0.8
+  /// Sets the sensitivities for the loaded hotwords, one value between 0 and 1
+  /// per hotword. For example, if there are 3 loaded hotwords, your values
+  /// should look something like this:
+  ///   [0.4, 0.5, 0.8]
   /// Make sure you properly align the sensitivity value to the corresponding
-  /// hotword.
+  /// hotword. An error is returned if the number of values does not match
+  /// NumHotwords().
   ///
   /// # Examples
   ///
   /// ```no_run
   /// # use rsnowboy::SnowboyDetect;
   ///
-  /// let detector = SnowboyDetect::new("resources/common.res", "resources/models/snowboy.umdl");
-  /// detector.set_sensitivity("1");
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// detector.set_sensitivity(vec![0.5])?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
   /// ```
-  pub fn set_sensitivity<S>(&self, sensitivity_str: S) where S: AsRef<str> {
+  pub fn set_sensitivity<S>(&mut self, sensitivities: S) -> Result<(), SnowboyError>
+    where S: TryInto<Sensitivities>, SnowboyError: From<S::Error> {
+    let sensitivities = sensitivities.try_into()?;
+    self.check_sensitivity_count(&sensitivities)?;
+    let sensitivity_str = to_cstring("sensitivity string", &sensitivities.to_string())?;
     unsafe {
-      rsnowboy::detect_set_sensitivity(self.rsnowboy_detect,
-                                       CString::new(sensitivity_str.as_ref()).unwrap().as_ptr())
+      error::catch(|error| rsnowboy::detect_set_sensitivity(self.rsnowboy_detect, sensitivity_str.as_ptr(), error))
+    }
+  }
+
+  /// Sets the sensitivity of a single hotword, keeping the others. Indices
+  /// start at 1, as in `DetectionResult::Hotword`.
+  pub fn set_hotword_sensitivity(&mut self, index: NonZeroU32, sensitivity: f32) -> Result<(), SnowboyError> {
+    let mut sensitivities = self.get_sensitivity()?;
+    sensitivities.set_hotword(index, sensitivity)?;
+    self.set_sensitivity(sensitivities)
+  }
+
+  /// Returns the sensitivities for the current hotwords.
+  pub fn get_sensitivity(&self) -> Result<Sensitivities, SnowboyError> {
+    let mut buffer: Vec<u8> = vec![0; 64];
+    loop {
+      let length = unsafe {
+        error::catch(|error| rsnowboy::detect_get_sensitivity(self.rsnowboy_detect,
+                                                              buffer.as_mut_ptr() as *mut c_char,
+                                                              buffer.len(), error))?
+      };
+      if length < buffer.len() {
+        buffer.truncate(length);
+        return String::from_utf8_lossy(&buffer).parse();
+      }
+      buffer.resize(length + 1, 0);
     }
   }
 
-  /// Returns the sensitivity string for the current hotwords.
-  pub fn get_sensitivity(&self) -> String {
+  /// Sets the high sensitivity for the loaded hotwords, one value between 0 and
+  /// 1 per hotword like SetSensitivity(). It is a second, more sensitive
+  /// threshold snowboy applies to universal models, which helps in noisy
+  /// environments. An error is returned if the number of values does not match
+  /// NumHotwords().
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// detector.set_high_sensitivity(vec![0.9])?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn set_high_sensitivity<S>(&mut self, high_sensitivities: S) -> Result<(), SnowboyError>
+    where S: TryInto<Sensitivities>, SnowboyError: From<S::Error> {
+    let high_sensitivities = high_sensitivities.try_into()?;
+    self.check_sensitivity_count(&high_sensitivities)?;
+    let high_sensitivity_str = to_cstring("high sensitivity string", &high_sensitivities.to_string())?;
     unsafe {
-      let ret = rsnowboy::detect_get_sensitivity(self.rsnowboy_detect);
-      let cstr = CStr::from_ptr(ret);
-      String::from_utf8_lossy(cstr.to_bytes()).to_string()
+      error::catch(|error| rsnowboy::detect_set_high_sensitivity(self.rsnowboy_detect,
+                                                                 high_sensitivity_str.as_ptr(), error))?;
+    }
+    self.high_sensitivity = Some(high_sensitivities);
+    Ok(())
+  }
+
+  /// Returns the high sensitivities last set through `set_high_sensitivity`.
+  /// snowboy has no way to read them back, so this is `None` until they have
+  /// been set on this detector.
+  pub fn high_sensitivity(&self) -> Option<&Sensitivities> {
+    self.high_sensitivity.as_ref()
+  }
+
+  fn check_sensitivity_count(&self, sensitivities: &Sensitivities) -> Result<(), SnowboyError> {
+    let expected = self.num_hotwords as usize;
+    if sensitivities.len() != expected {
+      return Err(SnowboyError::SensitivityCount { expected, found: sensitivities.len() });
     }
+    Ok(())
   }
 
   /// Applied a fixed gain to the input audio. In case you have a very weak
@@ -186,32 +464,31 @@ impl SnowboyDetect {
   /// ```no_run
   /// # use rsnowboy::SnowboyDetect;
   ///
-  /// let detector = SnowboyDetect::new("resources/common.res", "resources/models/snowboy.umdl");
-  /// detector.set_audio_gain(1 as f32);
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// detector.set_audio_gain(1 as f32)?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
   /// ```
-  pub fn set_audio_gain(&self, audio_gain: f32) {
+  pub fn set_audio_gain(&mut self, audio_gain: f32) -> Result<(), SnowboyError> {
     unsafe {
-      rsnowboy::detect_set_audio_gain(self.rsnowboy_detect, audio_gain)
+      error::catch(|error| rsnowboy::detect_set_audio_gain(self.rsnowboy_detect, audio_gain, error))
     }
   }
 
-  /// Writes the models to the model filenames specified in <model_str> in the
-  /// constructor. This overwrites the original model with the latest parameter
+  /// Writes the models to the model filenames given to the constructor, as they
+  /// were resolved then. This overwrites the original model with the latest parameter
   /// setting. You are supposed to call this function if you have updated the
   /// hotword sensitivities through SetSensitivity(), and you would like to store
   /// those values in the model as the default value.
-  pub fn update_model(&self) {
+  pub fn update_model(&mut self) -> Result<(), SnowboyError> {
     unsafe {
-      rsnowboy::detect_update_model(self.rsnowboy_detect)
+      error::catch(|error| rsnowboy::detect_update_model(self.rsnowboy_detect, error))
     }
   }
 
   /// Returns the number of the loaded hotwords. This helps you to figure the
   /// index of the hotwords.
   pub fn num_hotwords(&self) -> i32 {
-    unsafe {
-      rsnowboy::detect_num_hotwords(self.rsnowboy_detect)
-    }
+    self.num_hotwords
   }
 
   /// If <apply_frontend> is true, then apply frontend audio processing;
@@ -222,9 +499,9 @@ impl SnowboyDetect {
   /// processing, it may decrease the performance. The general rule of thumb is:
   ///   1. For personal models, set it to false.
   ///   2. For universal models, follow the instruction of each published model
-  pub fn apply_frontend(&self, apply_frontend: bool) {
+  pub fn apply_frontend(&mut self, apply_frontend: bool) -> Result<(), SnowboyError> {
     unsafe {
-      rsnowboy::detect_apply_frontend(self.rsnowboy_detect, apply_frontend)
+      error::catch(|error| rsnowboy::detect_apply_frontend(self.rsnowboy_detect, apply_frontend, error))
     }
   }
 
@@ -232,54 +509,181 @@ impl SnowboyDetect {
   /// values for the audio data. You should use this information to set up your
   /// audio capturing interface.
   pub fn sample_rate(&self) -> i32 {
-    unsafe {
-      rsnowboy::detect_sample_rate(self.rsnowboy_detect)
-    }
+    self.sample_rate
   }
 
   pub fn num_channels(&self) -> i32 {
-    unsafe {
-      rsnowboy::detect_num_channels(self.rsnowboy_detect)
-    }
+    self.num_channels
   }
 
   pub fn bits_per_sample(&self) -> i32 {
-    unsafe {
-      rsnowboy::detect_bits_per_sample(self.rsnowboy_detect)
-    }
+    self.bits_per_sample
   }
 
-  pub fn destroy(&self) {
-    unsafe {
-      rsnowboy::detect_destroy(self.rsnowboy_detect)
-    }
+  /// Returns the format of the audio the detector expects, from SampleRate(),
+  /// NumChannels() and BitsPerSample().
+  pub fn format(&self) -> AudioFormat {
+    AudioFormat::from_snowboy(self.sample_rate, self.num_channels, self.bits_per_sample)
+  }
+
+  /// Runs hotword detection over a whole WAV file, in chunks of 100 ms, and
+  /// returns the result of every chunk with its offset. The file must have the
+  /// format of the detector, see `format`, or `SnowboyError::FormatMismatch`
+  /// is returned.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// for detection in detector.detect_file("resources/ding.wav")? {
+  ///   if let Some(index) = detection.result.hotword() {
+  ///     println!("Hotword {} detected at sample {}", index, detection.sample_offset);
+  ///   }
+  /// }
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn detect_file<P>(&mut self, path: P) -> Result<Vec<Detection<DetectionResult>>, SnowboyError>
+    where P: AsRef<Path> {
+    self.detect_source(&mut WavReader::open(path)?)
+  }
+
+  /// Runs hotword detection over a whole `AudioSource`, in chunks of 100 ms,
+  /// and returns the result of every chunk with its offset, once the source
+  /// ends. The last chunk tells snowboy that the stream ended, as
+  /// `Chunker::finish` does. The source must have the format of the detector,
+  /// see `format`, or `SnowboyError::FormatMismatch` is returned.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  /// use rsnowboy::source::RawSource;
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// let mut source = RawSource::stdin(detector.format());
+  /// let detections = detector.detect_source(&mut source)?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn detect_source<A>(&mut self, source: &mut A) -> Result<Vec<Detection<DetectionResult>>, SnowboyError>
+    where A: AudioSource + ?Sized {
+    check_source_format(source, self.format())?;
+    // The empty chunk that may end the stream only tells about a hotword.
+    let hotword = |result: &DetectionResult| result.hotword().is_some();
+    source::detect_chunks(source, |chunk, is_end| self.detect_bytes(chunk, is_end), hotword)
+  }
+
+  /// Returns an iterator that runs hotword detection over `source` as it is
+  /// read, with the position of every result, see `events`. The source must
+  /// have the format of the detector, see `format`, or
+  /// `SnowboyError::FormatMismatch` is returned.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyDetect;
+  /// use rsnowboy::wav::WavReader;
+  ///
+  /// let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+  /// for event in detector.events(WavReader::open("resources/ding.wav")?)? {
+  ///   let event = event?;
+  ///   println!("{:?} at {:?}", event.kind, event.timestamp);
+  /// }
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn events<A: AudioSource>(&mut self, source: A) -> Result<Events<'_, A>, SnowboyError> {
+    check_source_format(&source, self.format())?;
+    Ok(Events::new(self, source))
+  }
+}
+
+impl Drop for SnowboyDetect {
+  fn drop(&mut self) {
+    destroy_detect(self.rsnowboy_detect)
   }
 }
 
 /// SnowboyVad class interface.
-#[derive(Debug, Copy, Clone)]
+///
+/// The VAD owns the underlying native object, which is released when the VAD
+/// is dropped.
+#[derive(Debug)]
 pub struct SnowboyVad {
-  rsnowboy_vad: *mut rsnowboy::RSnowboyVad
+  rsnowboy_vad: *mut rsnowboy::RSnowboyVad,
+  sample_rate: i32,
+  num_channels: i32,
+  bits_per_sample: i32,
+  converter: Option<Converter>,
+  _memory_file: Option<MemoryFile>,
 }
 
+// Same reasoning as for `SnowboyDetect`: mutation requires `&mut self` and the
+// `&self` methods only call const functions.
+unsafe impl Send for SnowboyVad {}
+
+unsafe impl Sync for SnowboyVad {}
+
 impl SnowboyVad {
   /// Constructor that takes a resource file. It shares the same resource file
-  /// with SnowboyDetect.
-  pub fn new<S>(resource_filename: S) -> Self where S: AsRef<str> {
+  /// with SnowboyDetect. The path is resolved and checked as in
+  /// `SnowboyDetect::new`.
+  ///
+  /// # Examples
+  ///
+  /// ```no_run
+  /// # use rsnowboy::SnowboyVad;
+  ///
+  /// let vad = SnowboyVad::new("resources/common.res")?;
+  /// # Ok::<(), rsnowboy::SnowboyError>(())
+  /// ```
+  pub fn new<P>(resource_filename: P) -> Result<Self, SnowboyError> where P: AsRef<Path> {
+    let resource_filename = resolve_path(resource_filename.as_ref())?;
+    Self::create(&resource_filename, None)
+  }
+
+  /// Constructor that takes the content of a resource file, see
+  /// `SnowboyDetect::from_bytes`.
+  pub fn from_bytes(resource: &[u8]) -> Result<Self, SnowboyError> {
+    check_resource_header(Path::new("<resource>"), resource)?;
+    let memory_file = MemoryFile::new("resource", resource)?;
+    let resource_filename = memory_file.path().to_path_buf();
+    Self::create(&resource_filename, Some(memory_file))
+  }
+
+  /// Constructor that loads the resource bundled with the crate,
+  /// resources/common.res, from the binary.
+  #[cfg(feature = "embedded-resources")]
+  pub fn embedded() -> Result<Self, SnowboyError> {
+    Self::from_bytes(crate::embedded::COMMON_RES)
+  }
+
+  fn create(resource_filename: &Path, memory_file: Option<MemoryFile>) -> Result<Self, SnowboyError> {
+    check_resource(resource_filename)?;
+    let resource = path_to_cstring(resource_filename)?;
+    let rsnowboy_vad = unsafe {
+      error::catch(|error| rsnowboy::vad_create(resource.as_ptr(), error))?
+    };
+    let mut vad = Self {
+      rsnowboy_vad,
+      sample_rate: 0,
+      num_channels: 0,
+      bits_per_sample: 0,
+      converter: None,
+      _memory_file: memory_file,
+    };
     unsafe {
-      let rsnowboy_vad =
-        rsnowboy::vad_create(
-          CString::new(resource_filename.as_ref()).unwrap().as_ptr());
-      Self {
-        rsnowboy_vad
-      }
+      vad.sample_rate = error::catch(|error| rsnowboy::vad_sample_rate(rsnowboy_vad, error))?;
+      vad.num_channels = error::catch(|error| rsnowboy::vad_num_channels(rsnowboy_vad, error))?;
+      vad.bits_per_sample = error::catch(|error| rsnowboy::vad_bits_per_sample(rsnowboy_vad, error))?;
     }
+    Ok(vad)
   }
 
   /// Resets the VAD.
-  pub fn reset(&self) -> bool {
+  pub fn reset(&mut self) -> Result<bool, SnowboyError> {
     unsafe {
-      rsnowboy::vad_reset(self.rsnowboy_vad)
+      error::catch(|error| rsnowboy::vad_reset(self.rsnowboy_vad, error))
     }
   }
 
@@ -291,64 +695,84 @@ impl SnowboyVad {
   /// call RunDetection(). Larger chunk usually leads to longer delay, but less
   /// CPU usage.
   ///
-  /// Definition of return values:
-  /// -2: Silence.
-  /// -1: Error.
-  ///  0: Non-silence.
+  /// The bytes are passed to snowboy as they are, together with their length,
+  /// so they may contain zero bytes. Returns the `VadResult` of the chunk, or
+  /// an error if snowboy reports one (-1).
   ///
   /// @param [in]  data               Small chunk of data to be detected. See
   ///                                 above for the supported data format.
   /// @param [in]  is_end             Set it to true if it is the end of a
   ///                                 utterance or file.
-  pub fn run_char(&self, data: *const ::std::os::raw::c_char) -> i32 {
-    unsafe {
-      rsnowboy::vad_run_char(self.rsnowboy_vad, data)
-    }
+  pub fn detect_bytes(&mut self, data: &[u8], is_end: bool) -> Result<VadResult, SnowboyError> {
+    check_block_aligned(data, self.bits_per_sample, self.num_channels)?;
+    let code = unsafe {
+      error::catch(|error| rsnowboy::vad_run_bytes(self.rsnowboy_vad, data.as_ptr() as *const c_char,
+                                                   data.len(), is_end, error))?
+    };
+    VadResult::from_code(code)
   }
 
-  /// Various versions of RunVad() that take different format of audio. If
-  /// NumChannels() > 1, e.g., NumChannels() == 2, then the array is as follows:
+  /// Runs the VAD over a slice of samples. This is the counterpart of the
+  /// various versions of RunVad() that take different format of audio, see
+  /// `Sample` for the supported sample types. If NumChannels() > 1, e.g.,
+  /// NumChannels() == 2, then the slice is as follows:
   ///
   ///   d1c1, d1c2, d2c1, d2c2, d3c1, d3c2, ..., dNc1, dNc2
   ///
   /// where d1c1 means data point 1 of channel 1.
   ///
-  /// @param [in]  data               Small chunk of data to be detected. See
+  /// Returns the `VadResult` of the chunk. An error is returned if `samples`
+  /// is not a whole number of frames, is too long to be passed to snowboy in
+  /// one call, or if snowboy reports an error (-1).
+  ///
+  /// @param [in]  samples            Small chunk of data to be detected. See
   ///                                 above for the supported data format.
-  /// @param [in]  array_length       Length of the data array.
   /// @param [in]  is_end             Set it to true if it is the end of a
   ///                                 utterance or file.
-  pub fn run_float_array(&self, data: *const f32, array_length: ::std::os::raw::c_int, is_end: bool) -> i32 {
-    unsafe {
-      rsnowboy::vad_run_float_array(self.rsnowboy_vad, data, array_length, is_end)
-    }
+  pub fn detect<S>(&mut self, samples: &[S], is_end: bool) -> Result<VadResult, SnowboyError> where S: Sample {
+    #[allow(deprecated)]
+    let code = self.detect_raw(samples, is_end)?;
+    VadResult::from_code(code)
   }
 
-  pub fn run_short_array(&self, data: *const i16, array_length: ::std::os::raw::c_int, is_end: bool) -> i32 {
-    unsafe {
-      rsnowboy::vad_run_short_array(self.rsnowboy_vad, data, array_length, is_end)
-    }
+  /// Runs the VAD over samples of any encoding, see
+  /// `SnowboyDetect::detect_buffer`.
+  pub fn detect_buffer(&mut self, buffer: AudioBuffer, is_end: bool) -> Result<VadResult, SnowboyError> {
+    let format = self.format();
+    let data = convert(&mut self.converter, format, buffer)?;
+    self.detect_bytes(&data, is_end)
   }
 
-  pub fn run_integer_array(&self, data: *const i32, array_length: ::std::os::raw::c_int, is_end: bool) -> i32 {
+  /// Sets how `detect_buffer` converts samples, see
+  /// `SnowboyDetect::set_converter`.
+  pub fn set_converter(&mut self, converter: Converter) -> Result<(), SnowboyError> {
+    self.converter = Some(check_converter(converter, self.format())?);
+    Ok(())
+  }
+
+  /// Same as `detect`, but returns the raw RunVad() code instead of a
+  /// `VadResult`.
+  #[deprecated(note = "use `detect`, which returns a `VadResult`")]
+  pub fn detect_raw<S>(&mut self, samples: &[S], is_end: bool) -> Result<i32, SnowboyError> where S: Sample {
+    let length = sample::array_length(samples.len(), self.num_channels())?;
     unsafe {
-      rsnowboy::vad_run_integer_array(self.rsnowboy_vad, data, array_length, is_end)
+      S::run_vad(self.rsnowboy_vad, samples, length, is_end)
     }
   }
 
   /// Applied a fixed gain to the input audio. In case you have a very weak
   /// microphone, you can use this function to boost input audio level.
-  pub fn set_audio_gain(&self, audio_gain: f32) {
+  pub fn set_audio_gain(&mut self, audio_gain: f32) -> Result<(), SnowboyError> {
     unsafe {
-      rsnowboy::vad_set_audio_gain(self.rsnowboy_vad, audio_gain)
+      error::catch(|error| rsnowboy::vad_set_audio_gain(self.rsnowboy_vad, audio_gain, error))
     }
   }
 
   /// If <apply_frontend> is true, then apply frontend audio processing;
   /// otherwise turns the audio processing off.
-  pub fn apply_frontend(&self, apply_frontend: bool) {
+  pub fn apply_frontend(&mut self, apply_frontend: bool) -> Result<(), SnowboyError> {
     unsafe {
-      rsnowboy::vad_apply_frontend(self.rsnowboy_vad, apply_frontend)
+      error::catch(|error| rsnowboy::vad_apply_frontend(self.rsnowboy_vad, apply_frontend, error))
     }
   }
 
@@ -356,26 +780,181 @@ impl SnowboyVad {
   /// values for the audio data. You should use this information to set up your
   /// audio capturing interface.
   pub fn sample_rate(&self) -> i32 {
-    unsafe {
-      rsnowboy::vad_sample_rate(self.rsnowboy_vad)
-    }
+    self.sample_rate
   }
 
   pub fn num_channels(&self) -> i32 {
-    unsafe {
-      rsnowboy::vad_num_channels(self.rsnowboy_vad)
-    }
+    self.num_channels
   }
 
   pub fn bits_per_sample(&self) -> i32 {
-    unsafe {
-      rsnowboy::vad_bits_per_sample(self.rsnowboy_vad)
-    }
+    self.bits_per_sample
   }
 
-  pub fn destroy(&self) {
-    unsafe {
-      rsnowboy::vad_destroy(self.rsnowboy_vad)
-    }
+  /// Returns the format of the audio the VAD expects.
+  pub fn format(&self) -> AudioFormat {
+    AudioFormat::from_snowboy(self.sample_rate, self.num_channels, self.bits_per_sample)
+  }
+
+  /// Runs the VAD over a whole WAV file, see `SnowboyDetect::detect_file`.
+  pub fn detect_file<P>(&mut self, path: P) -> Result<Vec<Detection<VadResult>>, SnowboyError> where P: AsRef<Path> {
+    self.detect_source(&mut WavReader::open(path)?)
   }
+
+  /// Runs the VAD over a whole `AudioSource`, see
+  /// `SnowboyDetect::detect_source`.
+  pub fn detect_source<A>(&mut self, source: &mut A) -> Result<Vec<Detection<VadResult>>, SnowboyError>
+    where A: AudioSource + ?Sized {
+    check_source_format(source, self.format())?;
+    source::detect_chunks(source, |chunk, is_end| self.detect_bytes(chunk, is_end), |_| false)
+  }
+}
+
+impl Drop for SnowboyVad {
+  fn drop(&mut self) {
+    destroy_vad(self.rsnowboy_vad)
+  }
+}
+
+/// Converts `buffer` with `converter`, which is created for `format` if it was
+/// not set.
+fn convert(converter: &mut Option<Converter>, format: AudioFormat,
+           buffer: AudioBuffer) -> Result<Vec<u8>, SnowboyError> {
+  let mut data = Vec::with_capacity(buffer.len() * format.bytes_per_sample());
+  converter.get_or_insert_with(|| Converter::new(format)).convert(buffer, &mut data)?;
+  Ok(data)
+}
+
+pub(crate) fn check_source_format<A: AudioSource + ?Sized>(source: &A, format: AudioFormat) -> Result<(), SnowboyError> {
+  if source.format() == format {
+    Ok(())
+  } else {
+    Err(SnowboyError::FormatMismatch { expected: format, found: source.format() })
+  }
+}
+
+fn check_converter(converter: Converter, format: AudioFormat) -> Result<Converter, SnowboyError> {
+  if converter.format() == format {
+    Ok(converter)
+  } else {
+    Err(SnowboyError::FormatMismatch { expected: format, found: converter.format() })
+  }
+}
+
+fn to_cstring(what: &str, value: &str) -> Result<CString, SnowboyError> {
+  CString::new(value)
+    .map_err(|_| SnowboyError::InvalidArgument(format!("{} contains a NUL byte: {:?}", what, value)))
+}
+
+/// Makes `path` absolute, so the detector does not depend on the working
+/// directory, and checks snowboy can take it: the model string it expects is
+/// separated by comma and is passed as a C string.
+fn resolve_path(path: &Path) -> Result<PathBuf, SnowboyError> {
+  check_path_bytes(path)?;
+  let resolved = fs::canonicalize(path)
+    .map_err(|source| SnowboyError::Resource { path: path.to_path_buf(), source })?;
+  check_path_bytes(&resolved)?;
+  Ok(resolved)
+}
+
+fn check_path_bytes(path: &Path) -> Result<(), SnowboyError> {
+  let bytes = path_bytes(path)?;
+  let reason = if bytes.contains(&b',') {
+    "snowboy can not load a path that contains a comma"
+  } else if bytes.contains(&0) {
+    "the path contains a NUL byte"
+  } else {
+    return Ok(());
+  };
+  Err(SnowboyError::InvalidPath { path: path.to_path_buf(), reason: reason.to_string() })
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Result<Vec<u8>, SnowboyError> {
+  use std::os::unix::ffi::OsStrExt;
+  Ok(path.as_os_str().as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Result<Vec<u8>, SnowboyError> {
+  match path.to_str() {
+    Some(path) => Ok(path.as_bytes().to_vec()),
+    None => Err(SnowboyError::InvalidPath { path: path.to_path_buf(), reason: "the path is not valid UTF-8".to_string() }),
+  }
+}
+
+fn path_to_cstring(path: &Path) -> Result<CString, SnowboyError> {
+  let bytes = path_bytes(path)?;
+  CString::new(bytes)
+    .map_err(|_| SnowboyError::InvalidPath { path: path.to_path_buf(), reason: "the path contains a NUL byte".to_string() })
+}
+
+/// Reads the first bytes of `path`, after checking it is a regular file that
+/// can be opened for reading.
+fn read_header(path: &Path) -> Result<Vec<u8>, SnowboyError> {
+  let resource_error = |source| SnowboyError::Resource { path: path.to_path_buf(), source };
+  let file = File::open(path).map_err(resource_error)?;
+  if !file.metadata().map_err(resource_error)?.is_file() {
+    return Err(resource_error(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file")));
+  }
+  let mut header = Vec::with_capacity(HEADER_SIZE);
+  file.take(HEADER_SIZE as u64).read_to_end(&mut header).map_err(resource_error)?;
+  Ok(header)
+}
+
+const HEADER_SIZE: usize = 32;
+
+// Kaldi style files start with this marker when they are stored in binary mode.
+const BINARY_MARKER: &[u8] = b"\0B";
+
+// snowboy reports most errors by throwing from a destructor, which terminates
+// the process instead of reaching the wrapper's exception handler. So every
+// file is checked here, before snowboy gets to see it.
+
+fn check_resource(path: &Path) -> Result<(), SnowboyError> {
+  check_resource_header(path, &read_header(path)?)
+}
+
+fn check_resource_header(path: &Path, header: &[u8]) -> Result<(), SnowboyError> {
+  if !header.starts_with(BINARY_MARKER) {
+    return Err(SnowboyError::Resource {
+      path: path.to_path_buf(),
+      source: io::Error::new(io::ErrorKind::InvalidData, "not a snowboy resource file"),
+    });
+  }
+  Ok(())
+}
+
+fn check_model(path: &Path) -> Result<(), SnowboyError> {
+  check_model_header(path, &read_header(path)?)
+}
+
+fn check_model_header(path: &Path, header: &[u8]) -> Result<(), SnowboyError> {
+  let body = header.strip_prefix(BINARY_MARKER).unwrap_or(header);
+  if !body.starts_with(b"<UniversalModel>") && !body.starts_with(b"<PersonalModel>") {
+    let reason = if header.is_empty() { "the file is empty" } else { "not a snowboy universal or personal model" };
+    return Err(SnowboyError::InvalidModel { path: path.to_path_buf(), reason: reason.to_string() });
+  }
+  Ok(())
+}
+
+/// snowboy rejects byte buffers that do not hold whole frames, and only logs
+/// why, so check it here to report a useful error.
+fn check_block_aligned(data: &[u8], bits_per_sample: i32, num_channels: i32) -> Result<(), SnowboyError> {
+  let block_size = (bits_per_sample.max(8) / 8 * num_channels.max(1)) as usize;
+  if data.len().is_multiple_of(block_size) {
+    Ok(())
+  } else {
+    Err(SnowboyError::InvalidArgument(
+      format!("{} bytes is not a whole number of {} bytes frames", data.len(), block_size)))
+  }
+}
+
+fn destroy_detect(detector: *mut rsnowboy::RSnowboyDetect) {
+  // Nothing can be done about a failure while releasing the detector.
+  let _ = unsafe { error::catch(|error| rsnowboy::detect_destroy(detector, error)) };
+}
+
+fn destroy_vad(vad: *mut rsnowboy::RSnowboyVad) {
+  let _ = unsafe { error::catch(|error| rsnowboy::vad_destroy(vad, error)) };
 }
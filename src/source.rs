@@ -0,0 +1,428 @@
+//! Where audio comes from.
+//!
+//! An `AudioSource` hands out chunks of audio in a known format until the end
+//! of the stream. The crate comes with sources for WAV files (`WavReader`),
+//! raw PCM from any `Read` such as stdin or a FIFO (`RawSource`), synthetic
+//! audio (`Generator`), and the output of a recording command such as
+//! arecord, sox or ffmpeg (`CommandSource`). None of them needs an audio
+//! device, so code built on them can be tested with files and generators.
+//!
+//! `SnowboyDetect::detect_source` and `SnowboyVad::detect_source` run
+//! detection over a whole source.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::process::Command;
+//!
+//! use rsnowboy::source::CommandSource;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let mut command = Command::new("arecord");
+//! command.args(["-q", "-t", "raw", "-f", "S16_LE", "-r", "16000", "-c", "1", "-d", "5"]);
+//! let mut source = CommandSource::spawn(command, detector.format())?;
+//! for detection in detector.detect_source(&mut source)? {
+//!   if let Some(index) = detection.result.hotword() {
+//!     println!("Hotword {} detected at sample {}", index, detection.sample_offset);
+//!   }
+//! }
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::io::{self, Read, Stdin};
+use std::process::{Child, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread;
+use std::time::Duration;
+
+use crate::convert::{AudioBuffer, Converter};
+use crate::error::SnowboyError;
+use crate::format::AudioFormat;
+use crate::wav::{self, Detection, WavReader};
+
+/// Length of the chunks `detect_source` feeds to the detector, in
+/// milliseconds.
+pub(crate) const CHUNK_MILLIS: u32 = 100;
+
+/// A stream of audio.
+pub trait AudioSource {
+  /// Returns the format of the audio, which does not change during the
+  /// stream.
+  fn format(&self) -> AudioFormat;
+
+  /// Fills the start of `buffer` with whole frames of audio, and returns the
+  /// number of bytes written. It blocks until at least one frame is there,
+  /// and returns 0 at the end of the stream. `buffer` must be able to hold a
+  /// frame.
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError>;
+}
+
+impl<A: AudioSource + ?Sized> AudioSource for &mut A {
+  fn format(&self) -> AudioFormat {
+    (**self).format()
+  }
+
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError> {
+    (**self).read_chunk(buffer)
+  }
+}
+
+impl<A: AudioSource + ?Sized> AudioSource for Box<A> {
+  fn format(&self) -> AudioFormat {
+    (**self).format()
+  }
+
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError> {
+    (**self).read_chunk(buffer)
+  }
+}
+
+impl<R: Read> AudioSource for WavReader<R> {
+  fn format(&self) -> AudioFormat {
+    WavReader::format(self)
+  }
+
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError> {
+    let frame_len = self.format().bytes_per_frame();
+    let len = whole_frames(buffer.len(), frame_len)?;
+    let read = wav::read_full(self, &mut buffer[..len])?;
+    // A truncated file may end with part of a frame, which is dropped.
+    Ok(read - read % frame_len)
+  }
+}
+
+/// Raw PCM read from any `Read`, e.g. stdin, a FIFO or a socket, in a format
+/// that is known beforehand.
+#[derive(Debug)]
+pub struct RawSource<R> {
+  reader: R,
+  format: AudioFormat,
+  /// Start of a frame that was read with the end of the previous chunk.
+  partial: Vec<u8>,
+}
+
+impl<R: Read> RawSource<R> {
+  pub fn new(reader: R, format: AudioFormat) -> Self {
+    Self { reader, format, partial: Vec::new() }
+  }
+
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+}
+
+impl RawSource<Stdin> {
+  /// Reads the audio from the standard input, e.g. `arecord ... | program`.
+  pub fn stdin(format: AudioFormat) -> Self {
+    Self::new(io::stdin(), format)
+  }
+}
+
+impl<R: Read> AudioSource for RawSource<R> {
+  fn format(&self) -> AudioFormat {
+    self.format
+  }
+
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError> {
+    let frame_len = self.format.bytes_per_frame();
+    let len = whole_frames(buffer.len(), frame_len)?;
+    let mut filled = self.partial.len();
+    buffer[..filled].copy_from_slice(&self.partial);
+    self.partial.clear();
+    // Pipes return what they have, which may not be a whole frame.
+    while filled < frame_len {
+      match self.reader.read(&mut buffer[filled..len]) {
+        // The end of a frame cut by the end of the stream is dropped.
+        Ok(0) => return Ok(0),
+        Ok(read) => filled += read,
+        Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+        Err(error) => return Err(SnowboyError::Io(error)),
+      }
+    }
+    let whole = filled - filled % frame_len;
+    self.partial.extend_from_slice(&buffer[whole..filled]);
+    Ok(whole)
+  }
+}
+
+/// Synthetic audio, computed sample by sample, e.g. for tests.
+pub struct Generator {
+  format: AudioFormat,
+  converter: Converter,
+  /// Length of the stream in frames, or `None` if it does not end.
+  frames: Option<u64>,
+  position: u64,
+  generate: Box<dyn FnMut(u64, usize) -> f32 + Send>,
+}
+
+impl Generator {
+  /// Creates a source of `frames` frames, or an endless one, where
+  /// `generate(frame, channel)` returns each sample, between -1 and 1.
+  /// `frame` counts from the start of the stream.
+  pub fn new<F>(format: AudioFormat, frames: Option<u64>, generate: F) -> Self
+    where F: FnMut(u64, usize) -> f32 + Send + 'static {
+    Self { format, converter: Converter::new(format), frames, position: 0, generate: Box::new(generate) }
+  }
+
+  /// Creates `millis` milliseconds of silence.
+  pub fn silence(format: AudioFormat, millis: u32) -> Self {
+    Self::new(format, Some(frames(format, millis)), |_, _| 0.0)
+  }
+
+  /// Creates `millis` milliseconds of a sine wave of `frequency` Hz, the same
+  /// on every channel, with a peak of `amplitude`.
+  pub fn sine(format: AudioFormat, frequency: f32, amplitude: f32, millis: u32) -> Self {
+    let sample_rate = f64::from(format.sample_rate);
+    Self::new(format, Some(frames(format, millis)), move |frame, _| {
+      amplitude * (2.0 * PI * f64::from(frequency) * frame as f64 / sample_rate).sin() as f32
+    })
+  }
+}
+
+impl fmt::Debug for Generator {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Generator")
+      .field("format", &self.format)
+      .field("frames", &self.frames)
+      .field("position", &self.position)
+      .finish()
+  }
+}
+
+impl AudioSource for Generator {
+  fn format(&self) -> AudioFormat {
+    self.format
+  }
+
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError> {
+    let frame_len = self.format.bytes_per_frame();
+    let mut count = (whole_frames(buffer.len(), frame_len)? / frame_len) as u64;
+    if let Some(frames) = self.frames {
+      count = count.min(frames - self.position);
+    }
+    let channels = self.format.num_channels as usize;
+    let mut samples = Vec::with_capacity(count as usize * channels);
+    for frame in self.position..self.position + count {
+      samples.extend((0..channels).map(|channel| (self.generate)(frame, channel)));
+    }
+    let mut data = Vec::with_capacity(count as usize * frame_len);
+    self.converter.convert(AudioBuffer::F32(&samples), &mut data)?;
+    buffer[..data.len()].copy_from_slice(&data);
+    self.position += count;
+    Ok(data.len())
+  }
+}
+
+/// Raw PCM written to the standard output of a child process, e.g. arecord,
+/// `sox -d -t raw ...` or `ffmpeg ... -f s16le -`.
+///
+/// The process is killed when the source is dropped. Once its output ends, an
+/// error is returned if it did not exit successfully, unless it was killed by
+/// a `KillHandle`.
+#[derive(Debug)]
+pub struct CommandSource {
+  process: Arc<Mutex<Process>>,
+  source: RawSource<ChildStdout>,
+}
+
+/// The process of a `CommandSource`, shared with its `KillHandle`s.
+#[derive(Debug)]
+struct Process {
+  child: Child,
+  killed: bool,
+}
+
+impl CommandSource {
+  /// Starts `command` with its standard output piped to the source. The
+  /// command must write raw samples in `format`.
+  pub fn spawn(mut command: Command, format: AudioFormat) -> Result<Self, SnowboyError> {
+    let mut child = command.stdout(Stdio::piped()).spawn().map_err(|error| {
+      SnowboyError::Io(io::Error::new(error.kind(), format!("can not run {:?}: {}", command.get_program(), error)))
+    })?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let process = Arc::new(Mutex::new(Process { child, killed: false }));
+    Ok(Self { process, source: RawSource::new(stdout, format) })
+  }
+
+  /// Returns the process id of the command.
+  pub fn id(&self) -> u32 {
+    self.lock().child.id()
+  }
+
+  /// Returns a handle that kills the command from another thread, e.g. to
+  /// stop a `Listener` that waits for a command that records nothing.
+  pub fn kill_handle(&self) -> KillHandle {
+    KillHandle { process: Arc::downgrade(&self.process) }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Process> {
+    self.process.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+
+  /// Waits for the command to exit, and returns its status and whether it was
+  /// killed by a `KillHandle`. The lock is only taken to poll the command, so
+  /// that a `KillHandle` never waits for it to exit.
+  fn wait(&self) -> io::Result<(ExitStatus, bool)> {
+    loop {
+      let mut process = self.lock();
+      if let Some(status) = process.child.try_wait()? {
+        return Ok((status, process.killed));
+      }
+      drop(process);
+      thread::sleep(WAIT_INTERVAL);
+    }
+  }
+}
+
+/// How often `CommandSource` checks whether its command exited, once its
+/// output ended.
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
+impl AudioSource for CommandSource {
+  fn format(&self) -> AudioFormat {
+    self.source.format()
+  }
+
+  fn read_chunk(&mut self, buffer: &mut [u8]) -> Result<usize, SnowboyError> {
+    let read = self.source.read_chunk(buffer)?;
+    if read == 0 {
+      let (status, killed) = self.wait()?;
+      if !status.success() && !killed {
+        return Err(SnowboyError::Io(io::Error::other(format!("the command failed with {}", status))));
+      }
+    }
+    Ok(read)
+  }
+}
+
+impl Drop for CommandSource {
+  fn drop(&mut self) {
+    let _ = self.lock().child.kill();
+    let _ = self.wait();
+  }
+}
+
+/// Kills the command of a `CommandSource`, see `CommandSource::kill_handle`.
+///
+/// The output of the command ends with it, and so does the source, which
+/// unblocks a read waiting for audio. Only the command itself is killed:
+/// processes it started, e.g. those of a shell, keep the output open until
+/// they exit.
+#[derive(Debug, Clone)]
+pub struct KillHandle {
+  process: Weak<Mutex<Process>>,
+}
+
+impl KillHandle {
+  /// Kills the command, unless it has exited or the source was dropped.
+  pub fn kill(&self) -> Result<(), SnowboyError> {
+    if let Some(process) = self.process.upgrade() {
+      let mut process = process.lock().unwrap_or_else(PoisonError::into_inner);
+      process.killed = true;
+      process.child.kill()?;
+    }
+    Ok(())
+  }
+}
+
+/// Reads an `AudioSource` in chunks of 100 ms, and runs detection over them:
+/// every full chunk with `is_end` false, then what is left of the stream, a
+/// partial chunk or nothing, with `is_end` true, as `Chunker::finish` does.
+/// This is how `detect_source`, `Events`, and so `Listener`, read a source.
+#[derive(Debug)]
+pub(crate) struct ChunkReader {
+  buffer: Vec<u8>,
+  bytes_per_frame: usize,
+  sample_offset: u64,
+}
+
+/// A chunk read by a `ChunkReader`, with the result of running detection over
+/// it.
+pub(crate) struct ReadChunk<T> {
+  pub(crate) result: T,
+  /// Offset of the first frame, in samples per channel.
+  pub(crate) sample_offset: u64,
+  /// Number of frames, 0 for the empty chunk that ends a stream on a chunk
+  /// boundary.
+  pub(crate) frames: usize,
+  pub(crate) is_end: bool,
+}
+
+impl ChunkReader {
+  pub(crate) fn new(format: AudioFormat) -> Self {
+    Self { buffer: vec![0; chunk_size(format)], bytes_per_frame: format.bytes_per_frame(), sample_offset: 0 }
+  }
+
+  /// Returns the offset of the next chunk, i.e. the number of frames read.
+  pub(crate) fn sample_offset(&self) -> u64 {
+    self.sample_offset
+  }
+
+  /// Reads the next chunk of `source`, and runs `detect` over it. The chunk
+  /// with `is_end` true is the last one of the stream.
+  pub(crate) fn read<A, T, F>(&mut self, source: &mut A, detect: F) -> Result<ReadChunk<T>, SnowboyError>
+    where A: AudioSource + ?Sized, F: FnOnce(&[u8], bool) -> Result<T, SnowboyError> {
+    let read = read_full_chunk(source, &mut self.buffer)?;
+    let is_end = read < self.buffer.len();
+    let result = detect(&self.buffer[..read], is_end)?;
+    let frames = read / self.bytes_per_frame;
+    let sample_offset = self.sample_offset;
+    self.sample_offset += frames as u64;
+    Ok(ReadChunk { result, sample_offset, frames, is_end })
+  }
+}
+
+/// Reads `source` with a `ChunkReader`, and returns the result of every chunk
+/// with audio. The empty chunk that ends a stream on a chunk boundary is only
+/// returned when `report_end` accepts its result, e.g. for a hotword.
+pub(crate) fn detect_chunks<A, T, F, E>(source: &mut A, mut detect: F,
+                                        report_end: E) -> Result<Vec<Detection<T>>, SnowboyError>
+  where A: AudioSource + ?Sized, F: FnMut(&[u8], bool) -> Result<T, SnowboyError>, E: Fn(&T) -> bool {
+  let mut reader = ChunkReader::new(source.format());
+  let mut detections = Vec::new();
+  loop {
+    let chunk = reader.read(source, &mut detect)?;
+    if chunk.frames > 0 || report_end(&chunk.result) {
+      detections.push(Detection { result: chunk.result, sample_offset: chunk.sample_offset });
+    }
+    if chunk.is_end {
+      return Ok(detections);
+    }
+  }
+}
+
+/// Returns the number of bytes of 100 ms of audio in `format`, the size of the
+/// chunks the crate feeds to detectors.
+pub(crate) fn chunk_size(format: AudioFormat) -> usize {
+  (format.sample_rate / (1000 / CHUNK_MILLIS)).max(1) as usize * format.bytes_per_frame()
+}
+
+/// Reads from `source` until `buffer`, a whole number of frames, is full or
+/// the stream ends, and returns the number of bytes read.
+pub(crate) fn read_full_chunk<A>(source: &mut A, buffer: &mut [u8]) -> Result<usize, SnowboyError>
+  where A: AudioSource + ?Sized {
+  let mut filled = 0;
+  while filled < buffer.len() {
+    match source.read_chunk(&mut buffer[filled..])? {
+      0 => break,
+      read => filled += read,
+    }
+  }
+  Ok(filled)
+}
+
+/// Returns the longest whole number of frames in `len` bytes.
+fn whole_frames(len: usize, frame_len: usize) -> Result<usize, SnowboyError> {
+  if frame_len == 0 || len < frame_len {
+    return Err(SnowboyError::InvalidArgument(format!("a buffer of {} bytes can not hold a frame of {} bytes",
+                                                     len, frame_len)));
+  }
+  Ok(len - len % frame_len)
+}
+
+fn frames(format: AudioFormat, millis: u32) -> u64 {
+  u64::from(format.sample_rate) * u64::from(millis) / 1000
+}
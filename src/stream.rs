@@ -0,0 +1,130 @@
+//! Hotword detection over a `futures::Stream`, with the `async` feature.
+//!
+//! snowboy blocks while it runs detection, which must not happen on the
+//! threads of an async runtime. `SnowboyDetect::into_stream` moves the
+//! detector to a thread of its own, which reads a stream of raw PCM chunks and
+//! returns a `DetectionStream` of the events of every 100 ms of audio, like
+//! `DetectorSink` does. The chunks can have any length.
+//!
+//! Events are sent through a bounded channel: once it is full, the detection
+//! thread stops reading chunks until the events are consumed. Dropping the
+//! `DetectionStream` stops the thread, which then drops the detector and the
+//! stream of chunks.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures::executor::block_on;
+//! use futures::{stream, StreamExt};
+//!
+//! use rsnowboy::events::EventKind;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let chunks = stream::iter(vec![vec![0u8; 3200], vec![0u8; 1234]]);
+//! let mut events = detector.into_stream(chunks, 16)?;
+//! block_on(async {
+//!   while let Some(event) = events.next().await {
+//!     let event = event?;
+//!     if event.kind == EventKind::Hotword {
+//!       println!("Hotword at {:?}", event.timestamp);
+//!     }
+//!   }
+//!   Ok::<(), rsnowboy::SnowboyError>(())
+//! })?;
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::mpsc as std_mpsc;
+use std::task::{Context, Poll};
+use std::thread;
+
+use futures::channel::{mpsc, oneshot};
+use futures::{executor, select, FutureExt, SinkExt, Stream, StreamExt};
+
+use crate::error::SnowboyError;
+use crate::events::DetectionEvent;
+use crate::snowboy::SnowboyDetect;
+
+/// Stream of the events of a stream of audio, see
+/// `SnowboyDetect::into_stream`.
+///
+/// It ends after the end of the audio, or after the first error.
+#[derive(Debug)]
+pub struct DetectionStream {
+  events: mpsc::Receiver<Result<DetectionEvent, SnowboyError>>,
+  /// Dropped with the stream, which stops the detection thread.
+  _cancel: oneshot::Sender<()>,
+}
+
+impl Stream for DetectionStream {
+  type Item = Result<DetectionEvent, SnowboyError>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.events.poll_next_unpin(cx)
+  }
+}
+
+impl SnowboyDetect {
+  /// Runs hotword detection over `chunks`, raw little-endian PCM in the
+  /// format of the detector, on a thread of its own, and returns the stream of
+  /// its events. At most `capacity` events wait to be consumed.
+  pub fn into_stream<S>(self, chunks: S, capacity: usize) -> Result<DetectionStream, SnowboyError>
+    where S: Stream + Send + 'static, S::Item: AsRef<[u8]> {
+    if capacity == 0 {
+      return Err(SnowboyError::InvalidArgument("the capacity of a detection stream can not be 0".to_string()));
+    }
+    // The channel has a slot for every sender on top of its buffer.
+    let (sender, events) = mpsc::channel(capacity - 1);
+    let (cancel, canceled) = oneshot::channel();
+    thread::Builder::new()
+      .name("snowboy-detect".to_string())
+      .spawn(move || executor::block_on(detect(self, chunks, sender, canceled)))?;
+    Ok(DetectionStream { events, _cancel: cancel })
+  }
+}
+
+/// Runs detection over `chunks` until they end, an error, or `canceled`.
+async fn detect<S>(mut detector: SnowboyDetect, chunks: S,
+                   mut sender: mpsc::Sender<Result<DetectionEvent, SnowboyError>>,
+                   canceled: oneshot::Receiver<()>)
+  where S: Stream, S::Item: AsRef<[u8]> {
+  let (events, received) = std_mpsc::channel();
+  let mut sink = match detector.sink_channel(events) {
+    Ok(sink) => sink,
+    Err(error) => {
+      let _ = sender.send(Err(error)).await;
+      return;
+    }
+  };
+  let chunks = chunks.fuse();
+  futures::pin_mut!(chunks);
+  // The receiver itself counts as terminated once the sender is dropped, which
+  // `select!` would skip.
+  let mut canceled = canceled.fuse();
+  loop {
+    let chunk = select! {
+      chunk = chunks.next() => chunk,
+      _ = canceled => return,
+    };
+    // Errors are sent by the sink too.
+    let done = match &chunk {
+      Some(chunk) => sink.write_all(chunk.as_ref()).is_err(),
+      None => {
+        let _ = sink.finish();
+        true
+      }
+    };
+    for event in received.try_iter() {
+      // Fails once the `DetectionStream` is dropped.
+      if sender.send(event).await.is_err() {
+        return;
+      }
+    }
+    if done {
+      return;
+    }
+  }
+}
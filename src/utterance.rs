@@ -0,0 +1,282 @@
+//! Recording what is said after a hotword.
+//!
+//! `UtteranceRecorder` takes each chunk of audio with the result of running
+//! detection over it. After a hotword it records the audio until the speaker
+//! stops, and returns it as an `Utterance`, e.g. for a speech to text service.
+//!
+//! Whether a chunk is speech comes from the detector itself, which reports
+//! silence, or from a `SnowboyVad`, see `UtteranceRecorder::push_with_vad`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rsnowboy::utterance::UtteranceRecorder;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let mut detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let mut recorder = UtteranceRecorder::for_format(detector.format())?
+//!   .trailing_silence(700)
+//!   .max_length(10_000);
+//! loop {
+//!   let chunk: Vec<i16> = vec![0; 1600]; // get voice from your device
+//!   let result = detector.detect(&chunk, false)?;
+//!   if let Some(utterance) = recorder.push(&chunk, &result)? {
+//!     println!("{} samples after hotword {}, {:?}", utterance.audio.len(), utterance.hotword,
+//!              utterance.end_reason);
+//!     break;
+//!   }
+//! }
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::num::NonZeroU32;
+
+use crate::detection::{DetectionResult, VadResult};
+use crate::error::SnowboyError;
+use crate::format::AudioFormat;
+use crate::preroll::PreRoll;
+use crate::sample::Sample;
+
+/// Why the recording of an utterance stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndReason {
+  /// The speaker stopped: speech was followed by the trailing silence.
+  Silence,
+  /// The utterance reached the maximum length.
+  MaxLength,
+  /// Nobody spoke after the hotword before the initial timeout.
+  Timeout,
+  /// The stream ended, see `UtteranceRecorder::finish`.
+  EndOfStream,
+}
+
+/// Audio recorded after a hotword.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Utterance<S> {
+  /// Index of the hotword that started the recording, see
+  /// `DetectionResult::Hotword`.
+  pub hotword: NonZeroU32,
+  /// Name of the hotword, when the detector knows it.
+  pub hotword_name: Option<String>,
+  /// Interleaved samples, from the pre-roll before the hotword to the end of
+  /// the utterance.
+  pub audio: Vec<S>,
+  /// Offset of the first frame of `audio` from the start of the stream, in
+  /// samples per channel.
+  pub start: u64,
+  /// Offset of the frame after the last one of `audio`.
+  pub end: u64,
+  pub end_reason: EndReason,
+}
+
+/// An utterance being recorded.
+#[derive(Debug, Clone)]
+struct Recording<S> {
+  hotword: NonZeroU32,
+  hotword_name: Option<String>,
+  audio: Vec<S>,
+  start: u64,
+  /// Offset of the end of the chunk the hotword was found in.
+  triggered: u64,
+  /// Number of frames of speech since the hotword.
+  speech: u64,
+  /// Number of frames of silence since the last speech.
+  silence: u64,
+}
+
+/// State machine that records an utterance after each hotword.
+///
+/// Durations are in milliseconds, and are counted from the end of the chunk
+/// the hotword was found in, so the checks are only as precise as the chunks.
+/// By default the recorder keeps 2 s of audio before the hotword, ends an
+/// utterance after 300 ms of speech and 700 ms of silence, gives up after 3 s
+/// without speech, and stops an utterance after 10 s.
+#[derive(Debug, Clone)]
+pub struct UtteranceRecorder<S> {
+  sample_rate: u32,
+  channels: usize,
+  min_speech: u64,
+  trailing_silence: u64,
+  max_length: u64,
+  initial_timeout: u64,
+  pre_roll: Option<PreRoll<S>>,
+  /// Number of frames pushed since the start of the stream.
+  offset: u64,
+  recording: Option<Recording<S>>,
+}
+
+impl<S: Sample> UtteranceRecorder<S> {
+  /// Creates a recorder for `channels` interleaved channels at `sample_rate`.
+  pub fn new(sample_rate: u32, channels: usize) -> Result<Self, SnowboyError> {
+    if sample_rate == 0 || channels == 0 {
+      return Err(SnowboyError::InvalidArgument(format!("can not record {} channels at {} Hz",
+                                                       channels, sample_rate)));
+    }
+    let recorder = Self {
+      sample_rate,
+      channels,
+      min_speech: 0,
+      trailing_silence: 0,
+      max_length: 0,
+      initial_timeout: 0,
+      pre_roll: None,
+      offset: 0,
+      recording: None,
+    };
+    Ok(recorder.pre_roll(2000)?.min_speech(300).trailing_silence(700).max_length(10_000).initial_timeout(3000))
+  }
+
+  /// Creates a recorder for samples in `format`, e.g. `SnowboyDetect::format`.
+  pub fn for_format(format: AudioFormat) -> Result<Self, SnowboyError> {
+    Self::new(format.sample_rate, format.num_channels as usize)
+  }
+
+  /// Sets how much audio before the end of the chunk with the hotword is
+  /// kept, so the utterance starts with the hotword. The utterance always
+  /// starts with the whole chunk, however short the pre-roll: 0 keeps only
+  /// that chunk.
+  pub fn pre_roll(mut self, millis: u32) -> Result<Self, SnowboyError> {
+    self.pre_roll = match millis {
+      0 => None,
+      millis => Some(PreRoll::new(millis, self.sample_rate, self.channels)?),
+    };
+    Ok(self)
+  }
+
+  /// Sets how much speech there must be for silence to end the utterance.
+  /// Shorter sounds, e.g. a cough, do not count as someone speaking.
+  pub fn min_speech(mut self, millis: u32) -> Self {
+    self.min_speech = self.frames(millis);
+    self
+  }
+
+  /// Sets how long the speaker must be silent for the utterance to end.
+  pub fn trailing_silence(mut self, millis: u32) -> Self {
+    self.trailing_silence = self.frames(millis);
+    self
+  }
+
+  /// Sets the longest an utterance can go on after the hotword.
+  pub fn max_length(mut self, millis: u32) -> Self {
+    self.max_length = self.frames(millis);
+    self
+  }
+
+  /// Sets how long to wait for speech after the hotword.
+  pub fn initial_timeout(mut self, millis: u32) -> Self {
+    self.initial_timeout = self.frames(millis);
+    self
+  }
+
+  /// Returns whether an utterance is being recorded.
+  pub fn is_recording(&self) -> bool {
+    self.recording.is_some()
+  }
+
+  /// Takes the next chunk of audio, which must be whole frames, with the
+  /// result of running detection over it. A hotword starts an utterance;
+  /// during an utterance silence counts as silence, and anything else,
+  /// including another hotword, as speech. Returns the utterance when it
+  /// ends.
+  pub fn push(&mut self, samples: &[S], result: &DetectionResult) -> Result<Option<Utterance<S>>, SnowboyError> {
+    self.step(samples, result, *result != DetectionResult::Silence)
+  }
+
+  /// Same as `push`, with speech and silence from the VAD rather than from
+  /// the detector. `result` is only used to find hotwords.
+  pub fn push_with_vad(&mut self, samples: &[S], result: &DetectionResult,
+                       vad: VadResult) -> Result<Option<Utterance<S>>, SnowboyError> {
+    self.step(samples, result, vad == VadResult::Speech)
+  }
+
+  /// Ends the utterance being recorded, if any, and starts a new stream.
+  pub fn finish(&mut self) -> Option<Utterance<S>> {
+    let utterance = self.recording.take().map(|recording| self.utterance(recording, EndReason::EndOfStream));
+    self.reset();
+    utterance
+  }
+
+  /// Drops the utterance being recorded and the pre-roll, and starts a new
+  /// stream at offset 0.
+  pub fn reset(&mut self) {
+    self.recording = None;
+    self.offset = 0;
+    if let Some(pre_roll) = &mut self.pre_roll {
+      pre_roll.reset();
+    }
+  }
+
+  fn step(&mut self, samples: &[S], result: &DetectionResult,
+          speech: bool) -> Result<Option<Utterance<S>>, SnowboyError> {
+    if !samples.len().is_multiple_of(self.channels) {
+      return Err(SnowboyError::PartialFrame { samples: samples.len(), channels: self.channels });
+    }
+    let frames = (samples.len() / self.channels) as u64;
+    let start = self.offset;
+    self.offset += frames;
+    if let Some(pre_roll) = &mut self.pre_roll {
+      pre_roll.push(samples)?;
+    }
+
+    let recording = match &mut self.recording {
+      Some(recording) => recording,
+      None => {
+        if let DetectionResult::Hotword { index, name } = result {
+          // A pre-roll shorter than the chunk is stretched to it.
+          let (audio, start) = match self.pre_roll.as_ref().map(PreRoll::snapshot) {
+            Some(snapshot) if snapshot.sample_offset <= start => (snapshot.samples, snapshot.sample_offset),
+            _ => (samples.to_vec(), start),
+          };
+          self.recording = Some(Recording {
+            hotword: *index,
+            hotword_name: name.clone(),
+            audio,
+            start,
+            triggered: self.offset,
+            speech: 0,
+            silence: 0,
+          });
+        }
+        return Ok(None);
+      }
+    };
+
+    recording.audio.extend_from_slice(samples);
+    if speech {
+      recording.speech += frames;
+      recording.silence = 0;
+    } else {
+      recording.silence += frames;
+    }
+    let elapsed = self.offset - recording.triggered;
+    let end_reason = if recording.speech >= self.min_speech && recording.silence >= self.trailing_silence {
+      Some(EndReason::Silence)
+    } else if recording.speech < self.min_speech && elapsed >= self.initial_timeout {
+      Some(EndReason::Timeout)
+    } else if elapsed >= self.max_length {
+      Some(EndReason::MaxLength)
+    } else {
+      None
+    };
+    Ok(end_reason.map(|end_reason| {
+      let recording = self.recording.take().expect("an utterance is being recorded");
+      self.utterance(recording, end_reason)
+    }))
+  }
+
+  fn utterance(&self, recording: Recording<S>, end_reason: EndReason) -> Utterance<S> {
+    let end = recording.start + (recording.audio.len() / self.channels) as u64;
+    Utterance {
+      hotword: recording.hotword,
+      hotword_name: recording.hotword_name,
+      audio: recording.audio,
+      start: recording.start,
+      end,
+      end_reason,
+    }
+  }
+
+  fn frames(&self, millis: u32) -> u64 {
+    u64::from(self.sample_rate) * u64::from(millis) / 1000
+  }
+}
@@ -0,0 +1,502 @@
+//! Reading and writing RIFF/WAVE files.
+//!
+//! `WavReader` parses the header of a WAV file and gives access to its
+//! samples. It supports linear PCM with 8, 16, 24 or 32 bits per sample and
+//! IEEE float with 32 or 64 bits, in the plain and the extensible format, and
+//! RF64 files larger than 4 GiB.
+//!
+//! `WavWriter` writes samples as they come, e.g. an utterance captured after a
+//! hotword, and only puts the file in place once it is complete.
+//!
+//! snowboy does not convert audio, so the file must have the format the
+//! detector expects, see `SnowboyDetect::format`. `SnowboyDetect::detect_file`
+//! and `SnowboyVad::detect_file` check it and run detection over a whole file.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rsnowboy::wav::WavReader;
+//! use rsnowboy::SnowboyDetect;
+//!
+//! let detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+//! let reader = WavReader::open("resources/ding.wav")?;
+//! reader.check_format(detector.format())?;
+//! # Ok::<(), rsnowboy::SnowboyError>(())
+//! ```
+
+use std::convert::TryFrom;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::SnowboyError;
+use crate::format::{AudioFormat, SampleFormat};
+use crate::memory;
+use crate::sample::Sample;
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// The sub format GUID of the extensible format is the format tag followed by
+/// these bytes.
+const EXTENSIBLE_GUID_SUFFIX: [u8; 14] = [
+  0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+/// The fmt and ds64 chunks are 40 bytes at most in the formats that are
+/// supported, so a much larger one is a corrupted file.
+const MAX_HEADER_CHUNK_SIZE: u32 = 1024;
+
+/// Size of the body of the ds64 chunk, without a table.
+const DS64_SIZE: u32 = 28;
+
+/// Length of the header written by `WavWriter`.
+const HEADER_LEN: usize = 12 + 8 + DS64_SIZE as usize + 8 + 16 + 8;
+
+/// Result of running detection over one chunk of audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection<T> {
+  /// The result snowboy returned for the chunk.
+  pub result: T,
+  /// Offset of the first frame of the chunk from the start of the audio, in
+  /// samples per channel.
+  pub sample_offset: u64,
+}
+
+/// Reads the samples of a WAV file.
+///
+/// `WavReader` implements `Read`, which returns the raw bytes of the samples,
+/// without the header.
+#[derive(Debug)]
+pub struct WavReader<R> {
+  reader: R,
+  format: AudioFormat,
+  data_len: Option<u64>,
+  remaining: Option<u64>,
+}
+
+impl WavReader<BufReader<File>> {
+  /// Opens a WAV file and reads its header.
+  pub fn open<P>(path: P) -> Result<Self, SnowboyError> where P: AsRef<Path> {
+    let path = path.as_ref();
+    let file = File::open(path)
+      .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    WavReader::new(BufReader::new(file))
+  }
+}
+
+impl<R> WavReader<R> where R: Read {
+  /// Reads the header from `reader`, leaving it at the first sample.
+  pub fn new(mut reader: R) -> Result<Self, SnowboyError> {
+    let mut riff = [0; 12];
+    reader.read_exact(&mut riff).map_err(header_error)?;
+    let rf64 = &riff[0..4] == b"RF64";
+    if (&riff[0..4] != b"RIFF" && !rf64) || &riff[8..12] != b"WAVE" {
+      return Err(SnowboyError::InvalidWav("not a RIFF/WAVE file".to_string()));
+    }
+
+    let mut format = None;
+    let mut ds64_data_len = None;
+    loop {
+      let mut chunk_header = [0; 8];
+      reader.read_exact(&mut chunk_header).map_err(header_error)?;
+      let id = [chunk_header[0], chunk_header[1], chunk_header[2], chunk_header[3]];
+      let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+      match &id {
+        b"fmt " => {
+          let body = read_header_chunk(&mut reader, &id, size)?;
+          format = Some(parse_format(&body)?);
+        }
+        b"ds64" if rf64 => {
+          let body = read_header_chunk(&mut reader, &id, size)?;
+          if body.len() < 24 {
+            return Err(SnowboyError::InvalidWav(format!("ds64 chunk of {} bytes is too short", body.len())));
+          }
+          let mut data_len = [0; 8];
+          data_len.copy_from_slice(&body[8..16]);
+          ds64_data_len = Some(u64::from_le_bytes(data_len));
+        }
+        b"data" => {
+          let format = format.ok_or_else(|| SnowboyError::InvalidWav("data chunk before fmt chunk".to_string()))?;
+          // The size of RF64 data is in the ds64 chunk. Other writers that
+          // stream audio may not know the size, and leave the largest one, so
+          // read until the end of the file in that case.
+          let data_len = if size == u32::MAX { ds64_data_len } else { Some(u64::from(size)) };
+          return Ok(Self { reader, format, data_len, remaining: data_len });
+        }
+        _ => {
+          let skip = u64::from(size) + u64::from(size % 2);
+          let skipped = io::copy(&mut (&mut reader).take(skip), &mut io::sink())?;
+          if skipped < skip {
+            return Err(header_error(io::ErrorKind::UnexpectedEof.into()));
+          }
+        }
+      }
+    }
+  }
+
+  /// Returns the format of the samples.
+  pub fn format(&self) -> AudioFormat {
+    self.format
+  }
+
+  /// Returns the number of frames, i.e. samples per channel, the header
+  /// announces, if it gives a size.
+  pub fn num_frames(&self) -> Option<u64> {
+    self.data_len.map(|len| len / self.format.bytes_per_frame() as u64)
+  }
+
+  /// Checks the file has the `expected` format, e.g. `SnowboyDetect::format`.
+  pub fn check_format(&self, expected: AudioFormat) -> Result<(), SnowboyError> {
+    if self.format != expected {
+      return Err(SnowboyError::FormatMismatch { expected, found: self.format });
+    }
+    Ok(())
+  }
+
+  /// Reads samples, whatever their format, as floats between -1 and 1. Returns
+  /// the number of samples read, which is 0 at the end of the data.
+  ///
+  /// A truncated file may end with part of a sample, for which
+  /// `SnowboyError::PartialFrame` is returned, counting bytes as samples.
+  pub fn read_f32(&mut self, samples: &mut [f32]) -> Result<usize, SnowboyError> {
+    let bytes_per_sample = self.format.bytes_per_sample();
+    let mut bytes = vec![0; samples.len() * bytes_per_sample];
+    let read = read_full(self, &mut bytes)?;
+    if !read.is_multiple_of(bytes_per_sample) {
+      return Err(SnowboyError::PartialFrame { samples: read, channels: bytes_per_sample });
+    }
+    let read = read / bytes_per_sample;
+    for (sample, bytes) in samples.iter_mut().zip(bytes.chunks_exact(bytes_per_sample)).take(read) {
+      *sample = decode_f32(bytes, self.format.sample_format);
+    }
+    Ok(read)
+  }
+
+  /// Returns the underlying reader.
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+}
+
+impl<R> Read for WavReader<R> where R: Read {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let max = match self.remaining {
+      Some(remaining) => remaining.min(buf.len() as u64) as usize,
+      None => buf.len(),
+    };
+    let read = self.reader.read(&mut buf[..max])?;
+    if let Some(remaining) = self.remaining.as_mut() {
+      *remaining -= read as u64;
+    }
+    Ok(read)
+  }
+}
+
+/// Writes samples to a WAV file.
+///
+/// The samples are written to a temporary file next to the destination, which
+/// `finalize` renames to it once the header has its final sizes. So the
+/// destination never holds a partial file, and a writer dropped without being
+/// finalized leaves nothing behind. Files with more than 4 GiB of samples are
+/// written as RF64.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rsnowboy::wav::WavWriter;
+/// use rsnowboy::SnowboyDetect;
+///
+/// let detector = SnowboyDetect::new("resources/common.res", &["resources/models/snowboy.umdl"])?;
+/// let utterance: Vec<i16> = vec![0; 16000];
+/// let mut writer = WavWriter::create("utterance.wav", detector.format())?;
+/// writer.write_samples(&utterance)?;
+/// writer.finalize()?;
+/// # Ok::<(), rsnowboy::SnowboyError>(())
+/// ```
+#[derive(Debug)]
+pub struct WavWriter {
+  writer: BufWriter<File>,
+  path: PathBuf,
+  temp_path: PathBuf,
+  format: AudioFormat,
+  data_len: u64,
+  finalized: bool,
+  buffer: Vec<u8>,
+}
+
+impl WavWriter {
+  /// Starts writing a file at `path` that holds samples in `format`, e.g.
+  /// `SnowboyDetect::format`.
+  pub fn create<P>(path: P, format: AudioFormat) -> Result<Self, SnowboyError> where P: AsRef<Path> {
+    check_supported(&format)?;
+    let path = path.as_ref().to_path_buf();
+    let name = path.file_name().ok_or_else(|| SnowboyError::InvalidPath {
+      path: path.clone(),
+      reason: "the path has no file name".to_string(),
+    })?;
+    let dir = match path.parent() {
+      Some(dir) if !dir.as_os_str().is_empty() => dir,
+      _ => Path::new("."),
+    };
+    let (file, temp_path) = memory::create_unique(dir, &format!("{}.tmp", name.to_string_lossy()), |path| {
+      OpenOptions::new().write(true).create_new(true).open(path)
+    })?;
+    let mut writer = Self {
+      writer: BufWriter::new(file),
+      path,
+      temp_path,
+      format,
+      data_len: 0,
+      finalized: false,
+      buffer: Vec::new(),
+    };
+    writer.writer.write_all(&header(format, 0)?)?;
+    Ok(writer)
+  }
+
+  pub fn format(&self) -> AudioFormat {
+    self.format
+  }
+
+  /// Returns the number of frames, i.e. samples per channel, written so far.
+  pub fn num_frames(&self) -> u64 {
+    self.data_len / self.format.bytes_per_frame() as u64
+  }
+
+  /// Appends samples, interleaved if there are several channels. The sample
+  /// type must match the format of the file: `u8` for 8-bits PCM, `i16` for
+  /// 16-bits, `i32` for 32-bits and `f32` for 32-bits float.
+  pub fn write_samples<S>(&mut self, samples: &[S]) -> Result<(), SnowboyError> where S: Sample {
+    let found = AudioFormat::new(self.format.sample_rate, self.format.num_channels, S::BITS_PER_SAMPLE,
+                                 S::SAMPLE_FORMAT);
+    if found != self.format {
+      return Err(SnowboyError::FormatMismatch { expected: self.format, found });
+    }
+    let channels = self.format.num_channels as usize;
+    if !samples.len().is_multiple_of(channels) {
+      return Err(SnowboyError::PartialFrame { samples: samples.len(), channels });
+    }
+    let mut buffer = std::mem::take(&mut self.buffer);
+    buffer.clear();
+    S::extend_le(samples, &mut buffer);
+    let result = self.write_data(&buffer);
+    self.buffer = buffer;
+    result
+  }
+
+  /// Appends samples that are already encoded in the format of the file, e.g.
+  /// read from a `WavReader` or a capture device. `bytes` must hold whole
+  /// frames.
+  pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SnowboyError> {
+    if !bytes.len().is_multiple_of(self.format.bytes_per_frame()) {
+      return Err(SnowboyError::InvalidArgument(format!("{} bytes is not a whole number of {} bytes frames",
+                                                       bytes.len(), self.format.bytes_per_frame())));
+    }
+    self.write_data(bytes)
+  }
+
+  fn write_data(&mut self, bytes: &[u8]) -> Result<(), SnowboyError> {
+    self.writer.write_all(bytes)?;
+    self.data_len += bytes.len() as u64;
+    Ok(())
+  }
+
+  /// Writes the final sizes to the header, and moves the file to its
+  /// destination, replacing any file there.
+  pub fn finalize(mut self) -> Result<(), SnowboyError> {
+    if self.data_len % 2 == 1 {
+      self.writer.write_all(&[0])?;
+    }
+    self.writer.flush()?;
+    let header = header(self.format, self.data_len)?;
+    let file = self.writer.get_mut();
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&header)?;
+    file.sync_all()?;
+    fs::rename(&self.temp_path, &self.path)?;
+    self.finalized = true;
+    Ok(())
+  }
+}
+
+impl Drop for WavWriter {
+  fn drop(&mut self) {
+    if !self.finalized {
+      let _ = fs::remove_file(&self.temp_path);
+    }
+  }
+}
+
+fn parse_format(body: &[u8]) -> Result<AudioFormat, SnowboyError> {
+  if body.len() < 16 {
+    return Err(SnowboyError::InvalidWav(format!("fmt chunk of {} bytes is too short", body.len())));
+  }
+  let u16_at = |offset: usize| u16::from_le_bytes([body[offset], body[offset + 1]]);
+  let mut format_tag = u16_at(0);
+  let num_channels = u16_at(2);
+  let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+  let block_align = u16_at(12);
+  let bits_per_sample = u16_at(14);
+
+  if format_tag == WAVE_FORMAT_EXTENSIBLE {
+    if body.len() < 40 {
+      return Err(SnowboyError::InvalidWav("extensible fmt chunk is too short".to_string()));
+    }
+    if body[26..40] != EXTENSIBLE_GUID_SUFFIX {
+      return Err(SnowboyError::InvalidWav("unknown extensible sub format".to_string()));
+    }
+    format_tag = u16_at(24);
+  }
+
+  let sample_format = match format_tag {
+    WAVE_FORMAT_PCM => SampleFormat::Int,
+    WAVE_FORMAT_IEEE_FLOAT => SampleFormat::Float,
+    tag => return Err(SnowboyError::InvalidWav(format!("unsupported format tag {:#06x}", tag))),
+  };
+  let format = AudioFormat::new(sample_rate, num_channels, bits_per_sample, sample_format);
+  check_supported(&format)?;
+  if block_align as usize != format.bytes_per_frame() {
+    return Err(SnowboyError::InvalidWav(format!("block align {} does not match {}", block_align, format)));
+  }
+  Ok(format)
+}
+
+fn check_supported(format: &AudioFormat) -> Result<(), SnowboyError> {
+  let supported = match format.sample_format {
+    SampleFormat::Int => [8, 16, 24, 32].contains(&format.bits_per_sample),
+    SampleFormat::Float => [32, 64].contains(&format.bits_per_sample),
+  };
+  if !supported {
+    let encoding = if format.sample_format == SampleFormat::Int { "PCM" } else { "float" };
+    return Err(SnowboyError::InvalidWav(format!("{}-bits {} samples are not supported", format.bits_per_sample,
+                                                encoding)));
+  }
+  if format.num_channels == 0 || format.sample_rate == 0 {
+    return Err(SnowboyError::InvalidWav("no channel or sample rate".to_string()));
+  }
+  // The header holds the size of a frame in 16 bits, and the bytes per second
+  // in 32 bits.
+  let block_align = u16::try_from(format.bytes_per_frame()).ok();
+  if block_align.and_then(|block_align| format.sample_rate.checked_mul(u32::from(block_align))).is_none() {
+    return Err(SnowboyError::InvalidWav(format!("{} has too many bytes per second", format)));
+  }
+  Ok(())
+}
+
+/// Returns the header `WavWriter` writes for a file holding `data_len` bytes
+/// of samples in `format`, e.g. to send a WAV file of a known length over the
+/// network. Files of more than 4 GiB get an RF64 header.
+///
+/// `WavWriter` writes it before the samples with a size of 0, and again with
+/// the final size in `finalize`, so it always has the same length: a JUNK
+/// chunk reserves the room of the ds64 chunk it becomes in RF64 files.
+///
+/// # Examples
+///
+/// ```
+/// use rsnowboy::wav::{self, WavReader};
+/// use rsnowboy::{AudioFormat, SampleFormat};
+///
+/// let format = AudioFormat::new(16000, 1, 16, SampleFormat::Int);
+/// let header = wav::header(format, 32000)?;
+/// assert_eq!(WavReader::new(&header[..])?.num_frames(), Some(16000));
+/// # Ok::<(), rsnowboy::SnowboyError>(())
+/// ```
+pub fn header(format: AudioFormat, data_len: u64) -> Result<Vec<u8>, SnowboyError> {
+  check_supported(&format)?;
+  let riff_len = 4 + (8 + u64::from(DS64_SIZE)) + (8 + 16) + 8 + data_len + data_len % 2;
+  let rf64 = riff_len > u64::from(u32::MAX);
+  let format_tag = match format.sample_format {
+    SampleFormat::Int => WAVE_FORMAT_PCM,
+    SampleFormat::Float => WAVE_FORMAT_IEEE_FLOAT,
+  };
+  let block_align = format.bytes_per_frame() as u16;
+
+  let mut header = Vec::with_capacity(HEADER_LEN);
+  if rf64 {
+    header.extend_from_slice(b"RF64");
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+  } else {
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(riff_len as u32).to_le_bytes());
+  }
+  header.extend_from_slice(b"WAVE");
+
+  header.extend_from_slice(if rf64 { b"ds64" } else { b"JUNK" });
+  header.extend_from_slice(&DS64_SIZE.to_le_bytes());
+  if rf64 {
+    header.extend_from_slice(&riff_len.to_le_bytes());
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header.extend_from_slice(&(data_len / u64::from(block_align)).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+  } else {
+    header.extend_from_slice(&[0; DS64_SIZE as usize]);
+  }
+
+  header.extend_from_slice(b"fmt ");
+  header.extend_from_slice(&16u32.to_le_bytes());
+  header.extend_from_slice(&format_tag.to_le_bytes());
+  header.extend_from_slice(&format.num_channels.to_le_bytes());
+  header.extend_from_slice(&format.sample_rate.to_le_bytes());
+  header.extend_from_slice(&(format.sample_rate * u32::from(block_align)).to_le_bytes());
+  header.extend_from_slice(&block_align.to_le_bytes());
+  header.extend_from_slice(&format.bits_per_sample.to_le_bytes());
+
+  header.extend_from_slice(b"data");
+  let data_size = if rf64 { u32::MAX } else { data_len as u32 };
+  header.extend_from_slice(&data_size.to_le_bytes());
+  Ok(header)
+}
+
+/// Reads the body of a chunk that describes the file, and its padding: chunks
+/// are aligned to 2 bytes, so odd sized ones are followed by a pad byte.
+fn read_header_chunk<R>(reader: &mut R, id: &[u8; 4], size: u32) -> Result<Vec<u8>, SnowboyError> where R: Read {
+  if size > MAX_HEADER_CHUNK_SIZE {
+    return Err(SnowboyError::InvalidWav(format!("{} chunk of {} bytes is too large",
+                                                String::from_utf8_lossy(id).trim(), size)));
+  }
+  let mut body = vec![0; size as usize + size as usize % 2];
+  reader.read_exact(&mut body).map_err(header_error)?;
+  body.truncate(size as usize);
+  Ok(body)
+}
+
+fn header_error(error: io::Error) -> SnowboyError {
+  if error.kind() == io::ErrorKind::UnexpectedEof {
+    SnowboyError::InvalidWav("the header is truncated".to_string())
+  } else {
+    SnowboyError::Io(error)
+  }
+}
+
+/// Reads until `buf` is full or the end of the data.
+pub(crate) fn read_full<R>(reader: &mut R, buf: &mut [u8]) -> Result<usize, SnowboyError> where R: Read {
+  let mut filled = 0;
+  while filled < buf.len() {
+    match reader.read(&mut buf[filled..]) {
+      Ok(0) => break,
+      Ok(read) => filled += read,
+      Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+      Err(error) => return Err(SnowboyError::Io(error)),
+    }
+  }
+  Ok(filled)
+}
+
+fn decode_f32(bytes: &[u8], sample_format: SampleFormat) -> f32 {
+  match (sample_format, bytes.len()) {
+    (SampleFormat::Int, 1) => (f32::from(bytes[0]) - 128.0) / 128.0,
+    (SampleFormat::Int, 2) => f32::from(i16::from_le_bytes([bytes[0], bytes[1]])) / 32768.0,
+    (SampleFormat::Int, 3) => (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8_388_608.0,
+    (SampleFormat::Int, 4) => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2_147_483_648.0,
+    (SampleFormat::Float, 4) => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    (SampleFormat::Float, 8) => {
+      let mut array = [0; 8];
+      array.copy_from_slice(bytes);
+      f64::from_le_bytes(array) as f32
+    }
+    _ => unreachable!("sample sizes are checked when the header is read"),
+  }
+}
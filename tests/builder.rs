@@ -0,0 +1,35 @@
+use std::num::NonZeroU32;
+
+use rsnowboy::{DetectorBuilder, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+#[test]
+fn models_set_the_sensitivity_of_their_hotwords() {
+  let mut detector = DetectorBuilder::new()
+    .resource(RESOURCE)
+    .model(MODEL, 0.4)
+    .model(MODEL, 0.6)
+    .high_sensitivity(vec![0.7, 0.9])
+    .audio_gain(2.0)
+    .build()
+    .unwrap();
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.4, 0.6]);
+  assert_eq!(detector.high_sensitivity().unwrap().as_slice(), &[0.7, 0.9]);
+  detector.set_hotword_sensitivity(NonZeroU32::new(2).unwrap(), 0.5).unwrap();
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.4, 0.5]);
+}
+
+#[test]
+fn settings_that_do_not_fit_are_errors() {
+  let builder = DetectorBuilder::new().resource(RESOURCE).model(MODEL, 0.5);
+  assert!(builder.clone().build().is_ok());
+  // High sensitivities are per hotword.
+  let result = builder.clone().high_sensitivity(vec![0.5, 0.5]).build();
+  assert!(matches!(result, Err(SnowboyError::SensitivityCount { expected: 1, found: 2 })));
+  assert!(matches!(builder.clone().model(MODEL, 1.5).build(), Err(SnowboyError::InvalidSensitivity(_))));
+  assert!(matches!(builder.clone().audio_gain(0.0).build(), Err(SnowboyError::InvalidArgument(_))));
+  assert!(matches!(DetectorBuilder::new().model(MODEL, 0.5).build(), Err(SnowboyError::InvalidArgument(_))));
+  assert!(matches!(DetectorBuilder::new().resource(RESOURCE).build(), Err(SnowboyError::InvalidArgument(_))));
+}
@@ -0,0 +1,102 @@
+use rsnowboy::channels::{self, ChannelAdapter, Layout, Mix};
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyDetect, SnowboyError, SnowboyVad};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn process(adapter: &ChannelAdapter, input: &[f32]) -> Vec<f32> {
+  let mut output = Vec::new();
+  adapter.process(input, &mut output).unwrap();
+  output
+}
+
+#[test]
+fn average_takes_the_mean_of_every_channel() {
+  let adapter = ChannelAdapter::new(4, 1, Mix::Average).unwrap();
+  assert_eq!(process(&adapter, &[1.0, 2.0, 3.0, 6.0, -1.0, -1.0, 1.0, 1.0]), vec![3.0, 0.0]);
+
+  let stereo = ChannelAdapter::new(2, 2, Mix::Average).unwrap();
+  assert_eq!(process(&stereo, &[0.5, -0.25, 0.0, 1.0]), vec![0.125, 0.125, 0.5, 0.5]);
+
+  let mut output = Vec::new();
+  ChannelAdapter::new(2, 1, Mix::Average).unwrap().process_i16(&[32767, 32767, -32768, -32767, 1, 2], &mut output)
+    .unwrap();
+  assert_eq!(output, vec![32767, -32768, 2]);
+}
+
+#[test]
+fn select_keeps_one_channel() {
+  let input = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+  for channel in 0..3 {
+    let adapter = ChannelAdapter::new(3, 1, Mix::Select(channel)).unwrap();
+    assert_eq!(process(&adapter, &input), vec![input[channel], input[3 + channel]]);
+  }
+  assert!(matches!(ChannelAdapter::new(3, 1, Mix::Select(3)), Err(SnowboyError::InvalidArgument(_))));
+  assert!(matches!(ChannelAdapter::new(0, 1, Mix::Select(0)), Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn weighted_sums_the_channels() {
+  let adapter = ChannelAdapter::new(3, 1, Mix::Weighted(vec![0.5, 0.25, -1.0])).unwrap();
+  assert_eq!(process(&adapter, &[1.0, 2.0, 0.5, 0.0, 4.0, 1.0]), vec![0.5, 0.0]);
+
+  for weights in [vec![1.0, 1.0], vec![1.0; 4], vec![]] {
+    let count = weights.len();
+    assert!(matches!(ChannelAdapter::new(3, 1, Mix::Weighted(weights)), Err(SnowboyError::InvalidArgument(_))),
+            "{} weights", count);
+  }
+  assert!(ChannelAdapter::new(2, 1, Mix::Weighted(vec![1.0, f32::NAN])).is_err());
+}
+
+#[test]
+fn planar_and_interleaved_round_trip() {
+  let left = [1, 2, 3];
+  let right = [-1, -2, -3];
+  let mut interleaved = Vec::new();
+  channels::interleave(&[&left[..], &right[..]], &mut interleaved).unwrap();
+  assert_eq!(interleaved, vec![1, -1, 2, -2, 3, -3]);
+  assert_eq!(channels::deinterleave(&interleaved, 2).unwrap(), vec![left.to_vec(), right.to_vec()]);
+
+  let samples = (0..12).collect::<Vec<i32>>();
+  for &count in &[1, 2, 3, 4, 6] {
+    let planar = channels::deinterleave(&samples, count).unwrap();
+    let planar = planar.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let mut back = Vec::new();
+    channels::interleave(&planar, &mut back).unwrap();
+    assert_eq!(back, samples, "{} channels", count);
+  }
+
+  assert!(matches!(channels::deinterleave(&samples, 5), Err(SnowboyError::PartialFrame { .. })));
+  assert!(matches!(channels::deinterleave(&samples, 0), Err(SnowboyError::PartialFrame { .. })));
+  assert!(channels::interleave(&[&left[..], &right[..2]], &mut Vec::new()).is_err());
+
+  // The three ways of giving planar audio to an adapter agree.
+  let mix = Mix::Weighted(vec![1.0, 0.5]);
+  let interleaved = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+  let planar = [1.0, 3.0, 5.0, 2.0, 4.0, 6.0];
+  let expected = process(&ChannelAdapter::new(2, 1, mix.clone()).unwrap(), &interleaved);
+  assert_eq!(expected, vec![2.0, 5.0, 8.0]);
+  let adapter = ChannelAdapter::new(2, 1, mix).unwrap().layout(Layout::Planar);
+  assert_eq!(process(&adapter, &planar), expected);
+  let mut output = Vec::new();
+  adapter.process_channels(&[&planar[..3], &planar[3..]], &mut output).unwrap();
+  assert_eq!(output, expected);
+}
+
+#[test]
+fn output_has_the_channels_of_the_detector() {
+  let detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let vad = SnowboyVad::new(RESOURCE).unwrap();
+  let input = (0..8 * 100).map(|i| i as f32).collect::<Vec<f32>>();
+  for (format, channels) in [(detector.format(), detector.num_channels()), (vad.format(), vad.num_channels())] {
+    let adapter = ChannelAdapter::for_format(8, format, Mix::Average).unwrap();
+    assert_eq!(adapter.output_channels(), channels as usize);
+    assert_eq!(process(&adapter, &input).len(), 100 * channels as usize);
+  }
+
+  let stereo = AudioFormat::new(16000, 2, 16, SampleFormat::Int);
+  let adapter = ChannelAdapter::for_format(8, stereo, Mix::Select(7)).unwrap();
+  assert_eq!(process(&adapter, &input[..16]), vec![7.0, 7.0, 15.0, 15.0]);
+  let mut output = Vec::new();
+  assert!(matches!(adapter.process(&input[..9], &mut output), Err(SnowboyError::PartialFrame { samples: 9, .. })));
+}
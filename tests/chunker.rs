@@ -0,0 +1,146 @@
+use rsnowboy::chunker::Chunker;
+use rsnowboy::wav::Detection;
+use rsnowboy::{DetectionResult, SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+/// A chunk passed to `detect`: its samples, and whether it was the end.
+type Chunk = (Vec<u8>, bool);
+
+fn record(chunk: &[u8], is_end: bool) -> Result<Chunk, SnowboyError> {
+  Ok((chunk.to_vec(), is_end))
+}
+
+/// Pushes `input` in pieces of `sizes`, cycled, and finishes the stream.
+fn split(chunker: &mut Chunker<u8>, input: &[u8], sizes: &[usize]) -> Vec<Detection<Chunk>> {
+  let mut detections = Vec::new();
+  let mut rest = input;
+  for &size in sizes.iter().cycle() {
+    if rest.is_empty() {
+      break;
+    }
+    let (piece, next) = rest.split_at(size.min(rest.len()));
+    detections.extend(chunker.push(piece, record).unwrap());
+    rest = next;
+  }
+  detections.push(chunker.finish(record).unwrap());
+  detections
+}
+
+#[test]
+fn chunks_do_not_depend_on_how_the_input_is_split() {
+  // 10 frames of 2 bytes per chunk.
+  let mut chunker = Chunker::<u8>::new(10, 1000, 2).unwrap();
+  assert_eq!(chunker.chunk_frames(), 10);
+  let input = (0..55 * 2).map(|i| i as u8).collect::<Vec<u8>>();
+
+  // Sizes that split frames, chunks, or both, and one larger than a chunk.
+  for sizes in [vec![1], vec![3], vec![7, 13], vec![20], vec![21], vec![45, 1, 2], vec![1000]] {
+    let detections = split(&mut chunker, &input, &sizes);
+    let offsets = detections.iter().map(|detection| detection.sample_offset).collect::<Vec<_>>();
+    assert_eq!(offsets, vec![0, 10, 20, 30, 40, 50], "{:?}", sizes);
+    let chunks = detections.into_iter().map(|detection| detection.result).collect::<Vec<_>>();
+    for (i, (chunk, is_end)) in chunks[..5].iter().enumerate() {
+      assert_eq!(chunk[..], input[i * 20..(i + 1) * 20], "{:?}", sizes);
+      assert!(!is_end);
+    }
+    // The partial chunk at the end is flushed as the end of the stream.
+    assert_eq!(chunks[5], (input[100..].to_vec(), true), "{:?}", sizes);
+  }
+}
+
+#[test]
+fn streams_ending_on_a_chunk_boundary_end_with_an_empty_chunk() {
+  let mut chunker = Chunker::<u8>::new(10, 1000, 2).unwrap();
+  let detections = split(&mut chunker, &[1; 40], &[15]);
+  let results = detections.iter()
+    .map(|detection| (detection.sample_offset, detection.result.1, detection.result.0.len()))
+    .collect::<Vec<_>>();
+  assert_eq!(results, vec![(0, false, 20), (10, false, 20), (20, true, 0)]);
+
+  // snowboy takes it.
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let mut chunker = Chunker::for_format(100, detector.format()).unwrap();
+  let detections = chunker.push(&[0; 3200 * 2], |chunk, is_end| detector.detect_bytes(chunk, is_end)).unwrap();
+  assert_eq!(detections.len(), 2);
+  let end = chunker.finish(|chunk, is_end| detector.detect_bytes(chunk, is_end)).unwrap();
+  assert_eq!((end.sample_offset, end.result), (3200, DetectionResult::Silence));
+}
+
+#[test]
+fn partial_frames_are_errors() {
+  let mut chunker = Chunker::<u8>::new(10, 1000, 2).unwrap();
+  assert_eq!(chunker.push(&[0; 25], record).unwrap().len(), 1);
+  assert_eq!(chunker.buffered(), 5);
+  let result = chunker.finish(record);
+  assert!(matches!(result, Err(SnowboyError::PartialFrame { samples: 5, channels: 2 })));
+  // The stream is dropped with the error.
+  assert_eq!((chunker.buffered(), chunker.sample_offset()), (0, 0));
+
+  assert!(matches!(Chunker::<u8>::new(10, 1000, 0), Err(SnowboyError::InvalidArgument(_))));
+  assert!(matches!(Chunker::<u8>::new(0, 16000, 2), Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn reset_starts_a_new_stream() {
+  let mut chunker = Chunker::<i16>::new(10, 1000, 1).unwrap();
+  let detections = chunker.push(&[1; 25], |chunk, is_end| Ok((chunk.len(), is_end))).unwrap();
+  assert_eq!(detections.len(), 2);
+  assert_eq!((chunker.sample_offset(), chunker.buffered()), (20, 5));
+
+  chunker.reset();
+  assert_eq!((chunker.sample_offset(), chunker.buffered()), (0, 0));
+  let detections = chunker.push(&[2; 10], |chunk, is_end| Ok((chunk.to_vec(), is_end))).unwrap();
+  assert_eq!(detections.len(), 1);
+  assert_eq!((detections[0].sample_offset, &detections[0].result), (0, &(vec![2; 10], false)));
+
+  // So does finish.
+  chunker.push(&[3; 4], |_, _| Ok(())).unwrap();
+  let end = chunker.finish(|chunk, is_end| Ok((chunk.to_vec(), is_end))).unwrap();
+  assert_eq!((end.sample_offset, end.result), (10, (vec![3; 4], true)));
+  assert_eq!(chunker.sample_offset(), 0);
+
+  // Errors from `detect` are returned.
+  let error = chunker.push(&[0; 10], |_, _| Err::<(), _>(SnowboyError::InvalidArgument("no".to_string())));
+  assert!(matches!(error.map_err(SnowboyError::from), Err(SnowboyError::InvalidArgument(_))));
+}
+
+/// Records chunks as `record` does, but fails on those that start with 0.
+fn fail_on_zero(chunk: &[u8], is_end: bool) -> Result<Chunk, SnowboyError> {
+  if chunk.first() == Some(&0) {
+    return Err(SnowboyError::InvalidArgument("zero".to_string()));
+  }
+  record(chunk, is_end)
+}
+
+#[test]
+fn failed_chunks_can_be_pushed_again() {
+  // Chunks of 10 bytes: 1, 2, then 0 that fails, and 4.
+  let mut chunker = Chunker::<u8>::new(5, 1000, 2).unwrap();
+  let input = [[1; 10], [2; 10], [0; 10], [4; 10]].concat();
+  assert!(chunker.push(&input[..5], fail_on_zero).unwrap().is_empty());
+  let error = chunker.push(&input[5..38], fail_on_zero).unwrap_err();
+  // The chunks before are kept, and the input stops at the chunk that failed.
+  let offsets = error.detections.iter().map(|detection| detection.sample_offset).collect::<Vec<_>>();
+  assert_eq!(offsets, vec![0, 5]);
+  assert_eq!(error.detections[1].result, (vec![2; 10], false));
+  assert_eq!(error.consumed, 15);
+  assert!(matches!(error.error, SnowboyError::InvalidArgument(_)));
+  assert_eq!((chunker.sample_offset(), chunker.buffered()), (10, 0));
+
+  // The rest is taken as if the push had stopped there.
+  let detections = chunker.push(&input[20..], record).unwrap();
+  let end = chunker.finish(record).unwrap();
+  assert_eq!(detections.iter().map(|detection| detection.sample_offset).collect::<Vec<_>>(), vec![10, 15]);
+  assert_eq!(detections[0].result.0, vec![0; 10]);
+  assert_eq!((end.sample_offset, end.result), (20, (vec![], true)));
+
+  // A buffered chunk that fails is left as it was.
+  chunker.push(&[0; 4], record).unwrap();
+  let error = chunker.push(&[0; 30], fail_on_zero).unwrap_err();
+  assert!(error.detections.is_empty());
+  assert_eq!(error.consumed, 0);
+  assert_eq!((chunker.sample_offset(), chunker.buffered()), (0, 4));
+  assert_eq!(chunker.push(&[5; 6], record).unwrap()[0].result.0, [&[0; 4][..], &[5; 6]].concat());
+}
@@ -0,0 +1,138 @@
+use rsnowboy::convert::{AudioBuffer, Clipping, Converter, Dither, Encoding, Endian};
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyError};
+
+fn format(bits: u16, sample_format: SampleFormat) -> AudioFormat {
+  AudioFormat::new(16000, 1, bits, sample_format)
+}
+
+fn convert(converter: &mut Converter, buffer: AudioBuffer) -> Vec<u8> {
+  let mut output = Vec::new();
+  converter.convert(buffer, &mut output).unwrap();
+  output
+}
+
+fn to_i16(bytes: &[u8]) -> Vec<i16> {
+  bytes.chunks(2).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])).collect()
+}
+
+fn bytes(encoding: Encoding, endian: Endian, data: &[u8]) -> AudioBuffer<'_> {
+  AudioBuffer::Bytes { encoding, endian, data }
+}
+
+#[test]
+fn every_encoding_decodes_to_the_same_samples() {
+  let mut converter = Converter::new(format(16, SampleFormat::Int));
+  let mut check = |buffer: AudioBuffer, expected: &[i16]| {
+    let label = format!("{:?} {:?}", buffer.encoding(), buffer);
+    assert_eq!(to_i16(&convert(&mut converter, buffer)), expected, "{}", label);
+  };
+
+  // 0x1234, -1 and -0.5 of full scale.
+  let expected = [0x1234, -1, -16384];
+  check(bytes(Encoding::I16, Endian::Little, &[0x34, 0x12, 0xff, 0xff, 0x00, 0xc0]), &expected);
+  check(bytes(Encoding::I16, Endian::Big, &[0x12, 0x34, 0xff, 0xff, 0xc0, 0x00]), &expected);
+  check(AudioBuffer::I16(&expected), &expected);
+  // Packed 24 bits, with the low byte rounded away.
+  check(bytes(Encoding::I24, Endian::Little, &[0x56, 0x34, 0x12, 0x00, 0xff, 0xff, 0x00, 0x00, 0xc0]), &expected);
+  check(bytes(Encoding::I24, Endian::Big, &[0x12, 0x34, 0x56, 0xff, 0xff, 0x00, 0xc0, 0x00, 0x00]), &expected);
+  let little = [0x78, 0x56, 0x34, 0x12, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00, 0x00, 0xc0];
+  check(bytes(Encoding::I32, Endian::Little, &little), &expected);
+  let big = [0x12, 0x34, 0x56, 0x78, 0xff, 0xff, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00];
+  check(bytes(Encoding::I32, Endian::Big, &big), &expected);
+  check(AudioBuffer::I32(&[0x1234_5678, -0x1_0000, -0x4000_0000]), &expected);
+
+  let floats = [0x1234 as f32 / 32768.0, -1.0 / 32768.0, -0.5];
+  let little = floats.iter().flat_map(|sample| sample.to_le_bytes()).collect::<Vec<u8>>();
+  let big = floats.iter().flat_map(|sample| sample.to_be_bytes()).collect::<Vec<u8>>();
+  check(bytes(Encoding::F32, Endian::Little, &little), &expected);
+  check(bytes(Encoding::F32, Endian::Big, &big), &expected);
+  check(AudioBuffer::F32(&floats), &expected);
+  let doubles = floats.iter().map(|&sample| f64::from(sample)).collect::<Vec<f64>>();
+  let big = doubles.iter().flat_map(|sample| sample.to_be_bytes()).collect::<Vec<u8>>();
+  check(bytes(Encoding::F64, Endian::Big, &big), &expected);
+  check(AudioBuffer::F64(&doubles), &expected);
+
+  // Unsigned 8 bits, centered on 128.
+  check(AudioBuffer::U8(&[0, 64, 128, 192, 255]), &[-32768, -16384, 0, 16384, 32512]);
+  check(bytes(Encoding::U8, Endian::Big, &[0, 128]), &[-32768, 0]);
+
+  let mut output = vec![1];
+  let partial = converter.convert(bytes(Encoding::I24, Endian::Little, &[0; 4]), &mut output);
+  assert!(matches!(partial, Err(SnowboyError::InvalidArgument(_))));
+  assert_eq!(output, vec![1]);
+}
+
+#[test]
+fn every_format_encodes_little_endian() {
+  let samples = AudioBuffer::F64(&[0.5, -1.0, 1.0, 0.1]);
+  let check = |bits: u16, sample_format: SampleFormat, expected: Vec<u8>| {
+    let mut converter = Converter::new(format(bits, sample_format));
+    assert_eq!(convert(&mut converter, samples), expected, "{}-bits {:?}", bits, sample_format);
+  };
+
+  check(8, SampleFormat::Int, vec![192, 0, 255, 141]);
+  check(16, SampleFormat::Int, vec![0x00, 0x40, 0x00, 0x80, 0xff, 0x7f, 0xcd, 0x0c]);
+  check(24, SampleFormat::Int, vec![0x00, 0x00, 0x40, 0x00, 0x00, 0x80, 0xff, 0xff, 0x7f, 0xcd, 0xcc, 0x0c]);
+  check(32, SampleFormat::Int, vec![
+    0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x80, 0xff, 0xff, 0xff, 0x7f, 0xcd, 0xcc, 0xcc, 0x0c,
+  ]);
+  // f64 is narrowed to the nearest f32.
+  check(32, SampleFormat::Float, [0.5f32, -1.0, 1.0, 0.1].iter().flat_map(|sample| sample.to_le_bytes()).collect());
+  check(64, SampleFormat::Float, [0.5f64, -1.0, 1.0, 0.1].iter().flat_map(|sample| sample.to_le_bytes()).collect());
+
+  for &(bits, sample_format) in &[(12, SampleFormat::Int), (16, SampleFormat::Float)] {
+    let mut converter = Converter::new(format(bits, sample_format));
+    let result = converter.convert(AudioBuffer::F32(&[0.0]), &mut Vec::new());
+    assert!(matches!(result, Err(SnowboyError::InvalidArgument(_))));
+  }
+}
+
+#[test]
+fn dither_stays_within_one_step() {
+  let samples = (0..10_000).map(|i| ((i as f32) * 0.01).sin() * 0.8).collect::<Vec<f32>>();
+  let plain = to_i16(&convert(&mut Converter::new(format(16, SampleFormat::Int)), AudioBuffer::F32(&samples)));
+  let mut converter = Converter::new(format(16, SampleFormat::Int)).dither(Dither::Triangular);
+  let dithered = to_i16(&convert(&mut converter, AudioBuffer::F32(&samples)));
+
+  let differences = plain.iter().zip(&dithered).map(|(&a, &b)| i32::from(b) - i32::from(a)).collect::<Vec<i32>>();
+  assert!(differences.iter().all(|difference| difference.abs() <= 1));
+  assert!(differences.iter().filter(|&&difference| difference != 0).count() > 1000);
+  // The noise has no offset.
+  assert!(differences.iter().sum::<i32>().abs() < 200);
+
+  // Full scale samples stay in range.
+  let edges = to_i16(&convert(&mut converter, AudioBuffer::F32(&[1.0; 100])));
+  assert!(edges.iter().all(|&sample| sample >= 32766));
+  let edges = to_i16(&convert(&mut converter, AudioBuffer::F32(&[-1.0; 100])));
+  assert!(edges.iter().all(|&sample| sample <= -32767));
+
+  // Nothing is lost when the output is as precise as the input.
+  let samples = (-1000..1000).map(|i| i * 31).collect::<Vec<i16>>();
+  assert_eq!(to_i16(&convert(&mut converter, AudioBuffer::I16(&samples))), samples);
+  let mut narrow = Converter::new(format(8, SampleFormat::Int)).dither(Dither::Triangular);
+  let output = convert(&mut narrow, AudioBuffer::I16(&samples));
+  for (&sample, &byte) in samples.iter().zip(&output) {
+    let exact = f64::from(sample) / 256.0 + 128.0;
+    assert!((f64::from(byte) - exact).abs() <= 1.5, "{} became {}", sample, byte);
+  }
+}
+
+#[test]
+fn clipping_saturates_or_fails() {
+  let samples = [1.5, -2.0, 1.0, -1.0, 0.25];
+  let mut saturate = Converter::new(format(16, SampleFormat::Int));
+  assert_eq!(to_i16(&convert(&mut saturate, AudioBuffer::F64(&samples))), vec![32767, -32768, 32767, -32768, 8192]);
+  let mut saturate = Converter::new(format(8, SampleFormat::Int)).clipping(Clipping::Saturate);
+  assert_eq!(convert(&mut saturate, AudioBuffer::F32(&[3.0, -3.0])), vec![255, 0]);
+
+  let mut strict = Converter::new(format(16, SampleFormat::Int)).clipping(Clipping::Error);
+  let mut output = vec![7, 7];
+  for sample in &[1.5, -2.0, f64::NAN] {
+    let result = strict.convert(AudioBuffer::F64(&[0.0, *sample]), &mut output);
+    assert!(matches!(result, Err(SnowboyError::InvalidArgument(_))), "{}", sample);
+    assert_eq!(output, vec![7, 7]);
+  }
+  assert_eq!(to_i16(&convert(&mut strict, AudioBuffer::F64(&samples[2..]))), vec![32767, -32768, 8192]);
+  // Integers always fit.
+  assert_eq!(to_i16(&convert(&mut strict, AudioBuffer::I32(&[i32::MAX, i32::MIN]))), vec![32767, -32768]);
+}
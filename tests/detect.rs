@@ -0,0 +1,53 @@
+use std::fs;
+
+use rsnowboy::{DetectionResult, SnowboyDetect, SnowboyError, SnowboyVad, VadResult};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+/// A loud square wave of 100 ms, whose every sample has a zero low byte.
+fn square_wave() -> Vec<i16> {
+  (0..1600).map(|i| if i / 20 % 2 == 0 { 0x4000 } else { -0x4000 }).collect()
+}
+
+fn to_bytes(samples: &[i16]) -> Vec<u8> {
+  samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()
+}
+
+#[test]
+fn bytes_are_not_cut_at_zero_bytes() {
+  let samples = square_wave();
+  let bytes = to_bytes(&samples);
+  assert_eq!(bytes[0], 0);
+
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let mut vad = SnowboyVad::new(RESOURCE).unwrap();
+  let results = (0..5).map(|_| detector.detect_bytes(&bytes, false).unwrap()).collect::<Vec<_>>();
+  assert!(results.contains(&DetectionResult::Sound), "{:?}", results);
+  let results = (0..5).map(|_| vad.detect_bytes(&bytes, false).unwrap()).collect::<Vec<_>>();
+  assert!(results.contains(&VadResult::Speech), "{:?}", results);
+
+  // The same as the samples, chunk by chunk, through the end of a file.
+  let data = &fs::read("resources/ding.wav").unwrap()[44..];
+  let mut by_bytes = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let mut by_samples = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let chunks = data.chunks(3200).collect::<Vec<_>>();
+  for (i, chunk) in chunks.iter().enumerate() {
+    let is_end = i == chunks.len() - 1;
+    let samples = chunk.chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect::<Vec<_>>();
+    assert_eq!(by_bytes.detect_bytes(chunk, is_end).unwrap(), by_samples.detect(&samples, is_end).unwrap());
+  }
+}
+
+#[test]
+fn bytes_must_be_whole_frames() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  assert!(matches!(detector.detect_bytes(&[0; 3], false), Err(SnowboyError::InvalidArgument(_))));
+  assert!(matches!(detector.detect_bytes(&[0; 3201], true), Err(SnowboyError::InvalidArgument(_))));
+  let mut vad = SnowboyVad::new(RESOURCE).unwrap();
+  assert!(matches!(vad.detect_bytes(&[0; 1], false), Err(SnowboyError::InvalidArgument(_))));
+
+  // The detector is still usable, and takes an empty end.
+  assert!(detector.detect_bytes(&to_bytes(&square_wave()), false).is_ok());
+  assert!(detector.detect_bytes(&[], true).is_ok());
+}
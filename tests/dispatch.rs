@@ -0,0 +1,95 @@
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rsnowboy::dispatch::{Dispatcher, Hotword};
+use rsnowboy::events::{DetectionEvent, EventKind};
+use rsnowboy::SnowboyError;
+
+fn event(kind: EventKind, hotword: Option<(u32, &str)>) -> Result<DetectionEvent, SnowboyError> {
+  Ok(DetectionEvent {
+    kind,
+    hotword_index: hotword.and_then(|(index, _)| NonZeroU32::new(index)),
+    hotword_name: hotword.map(|(_, name)| name.to_string()),
+    sample_offset: 0,
+    timestamp: Duration::from_secs(0),
+  })
+}
+
+/// Names of the handlers that were called.
+#[derive(Clone, Default)]
+struct Log(Arc<Mutex<Vec<&'static str>>>);
+
+impl Log {
+  fn push(&self, name: &'static str) {
+    self.0.lock().unwrap().push(name);
+  }
+
+  fn take(&self) -> Vec<&'static str> {
+    std::mem::take(&mut *self.0.lock().unwrap())
+  }
+}
+
+#[test]
+fn handlers_get_the_events_they_match() {
+  let dispatcher = Dispatcher::new();
+  let log = Log::default();
+  let (hotword, first, alexa, sound, silence, error, any) =
+    (log.clone(), log.clone(), log.clone(), log.clone(), log.clone(), log.clone(), log.clone());
+  let _hotword = dispatcher.on_hotword(Hotword::Any, move |_| hotword.push("hotword"));
+  let _first = dispatcher.on_hotword(NonZeroU32::new(1).unwrap(), move |_| first.push("first"));
+  let _alexa = dispatcher.on_hotword("alexa", move |_| alexa.push("alexa"));
+  let _sound = dispatcher.on_sound(move |_| sound.push("sound"));
+  let _silence = dispatcher.on_silence(move |_| silence.push("silence"));
+  let _error = dispatcher.on_error(move |_| error.push("error"));
+  let _any = dispatcher.on_any(move |_| any.push("any"));
+
+  dispatcher.dispatch(&event(EventKind::Hotword, Some((1, "snowboy"))));
+  assert_eq!(log.take(), ["hotword", "first", "any"]);
+  dispatcher.dispatch(&event(EventKind::Hotword, Some((2, "alexa"))));
+  assert_eq!(log.take(), ["hotword", "alexa", "any"]);
+  dispatcher.dispatch(&event(EventKind::SoundStart, None));
+  assert_eq!(log.take(), ["sound", "any"]);
+  dispatcher.dispatch(&event(EventKind::Silence, None));
+  assert_eq!(log.take(), ["silence", "any"]);
+  dispatcher.dispatch(&Err(SnowboyError::InvalidArgument("broken".to_string())));
+  assert_eq!(log.take(), ["error", "any"]);
+}
+
+#[test]
+fn subscriptions_remove_their_handler() {
+  let dispatcher = Dispatcher::new();
+  let log = Log::default();
+  let (sound, kept) = (log.clone(), log.clone());
+  let subscription = dispatcher.on_sound(move |_| sound.push("sound"));
+  dispatcher.on_sound(move |_| kept.push("kept")).detach();
+  assert_eq!(dispatcher.len(), 2);
+
+  drop(subscription);
+  assert_eq!(dispatcher.len(), 1);
+  dispatcher.dispatch(&event(EventKind::Sound, None));
+  assert_eq!(log.take(), ["kept"]);
+
+  // Handlers may unsubscribe themselves.
+  let slot = Arc::new(Mutex::new(None));
+  let own = slot.clone();
+  *slot.lock().unwrap() = Some(dispatcher.on_silence(move |_| {
+    own.lock().unwrap().take();
+  }));
+  dispatcher.dispatch(&event(EventKind::Silence, None));
+  assert_eq!(dispatcher.len(), 1);
+}
+
+#[test]
+fn panics_are_isolated() {
+  let dispatcher = Dispatcher::new();
+  let log = Log::default();
+  let good = log.clone();
+  let _bad = dispatcher.on_sound(|_| panic!("bad handler"));
+  let _good = dispatcher.on_sound(move |_| good.push("good"));
+
+  let mut callback = dispatcher.handler();
+  assert_eq!(dispatcher.dispatch(&event(EventKind::Sound, None)), 1);
+  callback(event(EventKind::Sound, None));
+  assert_eq!(log.take(), ["good", "good"]);
+}
@@ -0,0 +1,83 @@
+use std::io::{self, Read};
+use std::time::Duration;
+
+use rsnowboy::events::EventKind;
+use rsnowboy::source::{Generator, RawSource};
+use rsnowboy::wav::WavReader;
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+/// 4 s of silence, with sound from 1 s to 2.5 s.
+fn speech(format: AudioFormat) -> Generator {
+  Generator::new(format, Some(64000), |frame, _| {
+    if (16000..40000).contains(&frame) {
+      0.3 * (frame as f32 * 0.3).sin() + 0.2 * (frame as f32 * 0.05).sin()
+    } else {
+      0.0
+    }
+  })
+}
+
+#[test]
+fn every_chunk_has_an_event() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let events = detector.events(WavReader::open("resources/ding.wav").unwrap()).unwrap()
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+  let offsets = events.iter().map(|event| (event.sample_offset, event.timestamp)).collect::<Vec<_>>();
+  assert_eq!(offsets, (0..5).map(|i| (i * 1600, Duration::from_millis(i * 100))).collect::<Vec<_>>());
+  assert!(events.iter().all(|event| event.kind == EventKind::Silence && event.hotword_index.is_none()));
+}
+
+#[test]
+fn transitions_squash_runs_of_results() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let format = detector.format();
+  let all = detector.events(speech(format)).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+  assert_eq!(all.len(), 40);
+
+  let events = detector.events(speech(format)).unwrap().transitions()
+    .map(|event| event.map(|event| (event.kind, event.sample_offset, event.timestamp)))
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+  // snowboy hears the sound a chunk late, and its end 500 ms late.
+  assert_eq!(events, vec![
+    (EventKind::SoundStart, 17600, Duration::from_millis(1100)),
+    (EventKind::SoundEnd, 48000, Duration::from_millis(3000)),
+  ]);
+
+  // The end of the stream ends the sound.
+  let cut = Generator::new(format, Some(24000), |frame, _| {
+    if frame >= 16000 { (frame as f32 * 0.3).sin() } else { 0.0 }
+  });
+  let events = detector.events(cut).unwrap().transitions()
+    .map(|event| event.unwrap())
+    .collect::<Vec<_>>();
+  assert_eq!(events.last().map(|event| (event.kind, event.sample_offset)), Some((EventKind::SoundEnd, 24000)));
+}
+
+#[test]
+fn events_check_the_format() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let source = Generator::silence(AudioFormat::new(44100, 1, 16, SampleFormat::Int), 100);
+  assert!(matches!(detector.events(source), Err(SnowboyError::FormatMismatch { .. })));
+}
+
+#[test]
+fn events_stop_after_an_error() {
+  struct Broken;
+
+  impl Read for Broken {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+      Err(io::Error::new(io::ErrorKind::BrokenPipe, "the device is gone"))
+    }
+  }
+
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let format = detector.format();
+  let mut events = detector.events(RawSource::new(Broken, format)).unwrap();
+  assert!(matches!(events.next(), Some(Err(SnowboyError::Io(_)))));
+  assert!(events.next().is_none());
+}
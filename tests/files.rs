@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rsnowboy::{SnowboyDetect, SnowboyError, SnowboyVad};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("rsnowboy-test-{}-{}", std::process::id(), name));
+  fs::write(&path, bytes).unwrap();
+  path
+}
+
+fn canonical(path: &str) -> PathBuf {
+  fs::canonicalize(path).unwrap()
+}
+
+#[test]
+fn missing_files_are_named() {
+  match SnowboyDetect::new("resources/missing.res", &[MODEL]) {
+    Err(SnowboyError::Resource { path, .. }) => assert_eq!(path, Path::new("resources/missing.res")),
+    other => panic!("unexpected {:?}", other),
+  }
+  // The second of several models.
+  match SnowboyDetect::new(RESOURCE, &[MODEL, "resources/models/missing.umdl", MODEL]) {
+    Err(SnowboyError::Resource { path, .. }) => assert_eq!(path, Path::new("resources/models/missing.umdl")),
+    other => panic!("unexpected {:?}", other),
+  }
+  match SnowboyVad::new("resources/missing.res") {
+    Err(SnowboyError::Resource { path, .. }) => assert_eq!(path, Path::new("resources/missing.res")),
+    other => panic!("unexpected {:?}", other),
+  }
+  // A directory is not a file.
+  assert!(matches!(SnowboyDetect::new(RESOURCE, &["resources/models"]), Err(SnowboyError::Resource { .. })));
+  assert!(matches!(SnowboyDetect::new(RESOURCE, &[] as &[&str]), Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn files_that_are_not_models_are_named() {
+  // A WAV file, then the resource, given as a model.
+  for file in &["resources/ding.wav", RESOURCE] {
+    match SnowboyDetect::new(RESOURCE, &[MODEL, file]) {
+      Err(SnowboyError::InvalidModel { path, .. }) => assert_eq!(path, canonical(file)),
+      other => panic!("unexpected {:?} for {}", other, file),
+    }
+  }
+  let empty = fs::canonicalize(temp_file("empty.umdl", b"")).unwrap();
+  let result = SnowboyDetect::new(RESOURCE, &[&empty]);
+  fs::remove_file(&empty).unwrap();
+  match result {
+    Err(SnowboyError::InvalidModel { path, reason }) => {
+      assert_eq!(path, empty);
+      assert_eq!(reason, "the file is empty");
+    }
+    other => panic!("unexpected {:?}", other),
+  }
+
+  // And resources that are not resources.
+  match SnowboyVad::new("resources/ding.wav") {
+    Err(SnowboyError::Resource { path, .. }) => assert_eq!(path, canonical("resources/ding.wav")),
+    other => panic!("unexpected {:?}", other),
+  }
+  let error = SnowboyDetect::from_bytes(&fs::read(RESOURCE).unwrap(), &[b"RIFF"]).unwrap_err();
+  assert_eq!(error.to_string(), "invalid model <model-1>: not a snowboy universal or personal model");
+  assert!(SnowboyDetect::new(RESOURCE, &[MODEL]).is_ok());
+}
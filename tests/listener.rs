@@ -0,0 +1,144 @@
+use std::io::{self, Read};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use rsnowboy::events::EventKind;
+use rsnowboy::listener::Listener;
+use rsnowboy::source::{Generator, RawSource};
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn detector() -> SnowboyDetect {
+  SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap()
+}
+
+#[test]
+fn listeners_run_until_the_end_of_the_source() {
+  let detector = detector();
+  let source = Generator::silence(detector.format(), 1000);
+  let (sender, receiver) = mpsc::channel();
+  let listener = Listener::spawn(detector, source, sender).unwrap();
+  let events = receiver.iter().map(|event| event.unwrap()).collect::<Vec<_>>();
+  assert_eq!(events.iter().map(|event| event.sample_offset).collect::<Vec<_>>(),
+             (0..10).map(|i| i * 1600).collect::<Vec<_>>());
+  assert!(events.iter().all(|event| event.kind == EventKind::Silence));
+
+  let detector = listener.join();
+  assert_eq!(detector.format().sample_rate, 16000);
+}
+
+#[test]
+fn paused_listeners_do_not_read() {
+  let detector = detector();
+  let source = Generator::new(detector.format(), None, |_, _| 0.0);
+  let (sender, receiver) = mpsc::channel();
+  let listener = Listener::spawn(detector, source, sender).unwrap();
+  receiver.recv().unwrap().unwrap();
+
+  listener.pause();
+  assert!(listener.is_paused());
+  // The chunk being read when the listener was paused may still come.
+  thread::sleep(Duration::from_millis(50));
+  let before = receiver.try_iter().map(|event| event.unwrap().sample_offset).last();
+  thread::sleep(Duration::from_millis(200));
+  assert!(receiver.try_recv().is_err());
+
+  listener.resume();
+  assert!(!listener.is_paused());
+  let next = receiver.recv().unwrap().unwrap();
+  assert_eq!(next.sample_offset, before.map_or(1600, |offset| offset + 1600));
+
+  listener.stop();
+  // The thread has dropped the sender, which ends the events.
+  assert!(receiver.iter().all(|event| event.is_ok()));
+}
+
+#[test]
+fn stopping_a_paused_listener() {
+  let detector = detector();
+  let source = Generator::new(detector.format(), None, |_, _| 0.0);
+  let (sender, receiver) = mpsc::channel();
+  let listener = Listener::spawn(detector, source, sender).unwrap();
+  listener.pause();
+  listener.resume();
+  listener.pause();
+  listener.stop();
+  assert!(receiver.iter().all(|event| event.is_ok()));
+}
+
+#[test]
+fn errors_are_events() {
+  struct Broken;
+
+  impl Read for Broken {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+      Err(io::Error::new(io::ErrorKind::BrokenPipe, "the device is gone"))
+    }
+  }
+
+  let format = detector().format();
+  let (sender, receiver) = mpsc::channel();
+  let listener = Listener::spawn(detector(), RawSource::new(Broken, format), sender).unwrap();
+  let events = receiver.iter().collect::<Vec<_>>();
+  assert_eq!(events.len(), 1);
+  assert!(matches!(events[0], Err(SnowboyError::Io(_))));
+  listener.join();
+
+  let (sender, _receiver) = mpsc::channel();
+  let source = Generator::silence(AudioFormat::new(8000, 1, 16, SampleFormat::Int), 100);
+  assert!(matches!(Listener::spawn(detector(), source, sender), Err(SnowboyError::FormatMismatch { .. })));
+}
+
+#[cfg(unix)]
+#[test]
+fn killing_the_command_stops_a_blocked_listener() {
+  use std::process::Command;
+  use std::time::Instant;
+
+  use rsnowboy::source::CommandSource;
+
+  // A command that records nothing.
+  let detector = detector();
+  let mut command = Command::new("sleep");
+  command.arg("30");
+  let source = CommandSource::spawn(command, detector.format()).unwrap();
+  let kill = source.kill_handle();
+  let (sender, receiver) = mpsc::channel();
+  let listener = Listener::spawn(detector, source, sender).unwrap();
+  thread::sleep(Duration::from_millis(50));
+
+  let start = Instant::now();
+  kill.kill().unwrap();
+  listener.stop();
+  assert!(start.elapsed() < Duration::from_secs(10));
+  // The stream ended without an error.
+  assert_eq!(receiver.iter().count(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn dropping_a_listener_does_not_wait_for_its_thread() {
+  use std::process::Command;
+  use std::time::Instant;
+
+  use rsnowboy::source::CommandSource;
+
+  let detector = detector();
+  let mut command = Command::new("sleep");
+  command.arg("2");
+  let source = CommandSource::spawn(command, detector.format()).unwrap();
+  let (sender, receiver) = mpsc::channel();
+  let listener = Listener::spawn(detector, source, sender).unwrap();
+  // Let the thread block on the pipe.
+  thread::sleep(Duration::from_millis(50));
+
+  let start = Instant::now();
+  drop(listener);
+  assert!(start.elapsed() < Duration::from_secs(1));
+  // The thread ends when the command does, and drops the sender.
+  assert_eq!(receiver.iter().count(), 0);
+  assert!(start.elapsed() >= Duration::from_millis(1500));
+}
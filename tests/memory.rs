@@ -0,0 +1,45 @@
+use std::fs;
+
+use rsnowboy::{SnowboyDetect, SnowboyVad};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+/// Counts the files `from_bytes` made for this process: memfds where the
+/// system has them, temporary files otherwise.
+fn backing_files() -> usize {
+  let memfds = fs::read_dir("/proc/self/fd").into_iter().flatten().flatten()
+    .filter(|entry| fs::read_link(entry.path())
+      .is_ok_and(|target| target.to_string_lossy().contains("memfd:rsnowboy-")))
+    .count();
+  let prefix = format!("rsnowboy-{}-", std::process::id());
+  let temporary = fs::read_dir(std::env::temp_dir()).unwrap().flatten()
+    .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+    .count();
+  memfds + temporary
+}
+
+// A single test, so that no other test of this file creates files meanwhile.
+#[test]
+fn backing_files_live_as_long_as_the_detector() {
+  let resource = fs::read(RESOURCE).unwrap();
+  let model = fs::read(MODEL).unwrap();
+  assert_eq!(backing_files(), 0);
+
+  let mut detector = SnowboyDetect::from_bytes(&resource, &[&model, &model]).unwrap();
+  assert_eq!(detector.num_hotwords(), 2);
+  assert_eq!(backing_files(), 3);
+  let vad = SnowboyVad::from_bytes(&resource).unwrap();
+  assert_eq!(backing_files(), 4);
+  assert!(detector.detect(&[0i16; 1600], false).is_ok());
+
+  drop(detector);
+  assert_eq!(backing_files(), 1);
+  drop(vad);
+  assert_eq!(backing_files(), 0);
+
+  // Nor are they left behind by errors.
+  assert!(SnowboyDetect::from_bytes(&resource, &[&model, b"not a model"]).is_err());
+  assert!(SnowboyDetect::from_bytes(b"RIFF", &[&model]).is_err());
+  assert_eq!(backing_files(), 0);
+}
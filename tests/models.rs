@@ -0,0 +1,74 @@
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use rsnowboy::{HotwordModel, HotwordModels, SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn index(index: u32) -> NonZeroU32 {
+  NonZeroU32::new(index).unwrap()
+}
+
+/// A personal model file, only for its name: snowboy never loads it.
+fn personal_model(name: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("rsnowboy-test-{}-{}.pmdl", std::process::id(), name));
+  fs::copy(MODEL, &path).unwrap();
+  path
+}
+
+#[test]
+fn hotwords_are_numbered_across_models() {
+  let personal = personal_model("alexa");
+  let mut models = HotwordModels::new();
+  models.add(HotwordModel::new(MODEL, vec!["snowboy", "jarvis"]).with_sensitivity(0.4)).unwrap();
+  models.add(HotwordModel::new(&personal, vec!["alexa"])).unwrap();
+  fs::remove_file(&personal).unwrap();
+
+  assert_eq!(models.num_hotwords(), 3);
+  assert_eq!(models.hotwords().collect::<Vec<_>>(), vec!["snowboy", "jarvis", "alexa"]);
+  assert_eq!(models.lookup(index(1)), Some("snowboy"));
+  assert_eq!(models.lookup(index(2)), Some("jarvis"));
+  assert_eq!(models.lookup(index(3)), Some("alexa"));
+  assert_eq!(models.lookup(index(4)), None);
+  assert_eq!(models.sensitivities().as_slice(), &[0.4, 0.4, 0.5]);
+  assert_eq!(models.files().collect::<Vec<_>>(), vec![PathBuf::from(MODEL).as_path(), personal.as_path()]);
+}
+
+#[test]
+fn models_that_do_not_fit_are_rejected() {
+  let personal = personal_model("twice");
+  let mut models = HotwordModels::new();
+  let result = models.add(HotwordModel::new(&personal, vec!["alexa", "jarvis"]));
+  fs::remove_file(&personal).unwrap();
+  match result {
+    Err(SnowboyError::InvalidModel { path, .. }) => assert_eq!(path, personal),
+    other => panic!("unexpected {:?}", other),
+  }
+
+  let result = models.add(HotwordModel::new(MODEL, Vec::<String>::new()));
+  assert!(matches!(result, Err(SnowboyError::InvalidModel { .. })));
+  let result = models.add(HotwordModel::new("resources/models/missing.umdl", vec!["snowboy"]));
+  assert!(matches!(result, Err(SnowboyError::Resource { .. })));
+  let result = models.add(HotwordModel::new(MODEL, vec!["snowboy"]).with_sensitivity(1.5));
+  assert!(matches!(result, Err(SnowboyError::InvalidSensitivity(_))));
+  // Nothing was added.
+  assert_eq!(models.num_hotwords(), 0);
+}
+
+#[test]
+fn detectors_check_the_declared_hotwords() {
+  let mut models = HotwordModels::new();
+  models.add(HotwordModel::new(MODEL, vec!["snowboy"]).with_sensitivity(0.6)).unwrap();
+  let detector = SnowboyDetect::with_models(RESOURCE, models.clone()).unwrap();
+  assert_eq!(detector.models(), Some(&models));
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.6]);
+  assert!(SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap().models().is_none());
+
+  // snowboy.umdl holds a single hotword.
+  let mut models = HotwordModels::new();
+  models.add(HotwordModel::new(MODEL, vec!["snowboy", "jarvis"])).unwrap();
+  let result = SnowboyDetect::with_models(RESOURCE, models);
+  assert!(matches!(result, Err(SnowboyError::HotwordCount { declared: 2, loaded: 1 })));
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rsnowboy::{SnowboyDetect, SnowboyError, SnowboyVad};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn temp_dir(name: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("rsnowboy-test-{}-{}", std::process::id(), name));
+  fs::create_dir_all(&path).unwrap();
+  fs::canonicalize(path).unwrap()
+}
+
+fn invalid_path<T: std::fmt::Debug>(result: Result<T, SnowboyError>) -> PathBuf {
+  match result {
+    Err(SnowboyError::InvalidPath { path, .. }) => path,
+    other => panic!("unexpected {:?}", other),
+  }
+}
+
+#[test]
+fn commas_and_nul_bytes_are_rejected() {
+  let dir = temp_dir("a,b");
+  let model = dir.join("snowboy.umdl");
+  fs::copy(MODEL, &model).unwrap();
+  let resource = dir.join("common.res");
+  fs::copy(RESOURCE, &resource).unwrap();
+  let model_result = SnowboyDetect::new(RESOURCE, &[MODEL, model.to_str().unwrap()]);
+  let resource_result = SnowboyVad::new(&resource);
+  fs::remove_dir_all(&dir).unwrap();
+  assert_eq!(invalid_path(model_result), model);
+  assert_eq!(invalid_path(resource_result), resource);
+
+  let path = Path::new("resources/models/snowboy.umdl\0.pmdl");
+  assert_eq!(invalid_path(SnowboyDetect::new(RESOURCE, &[path])), path);
+  assert_eq!(invalid_path(SnowboyVad::new("resources/\0common.res")), Path::new("resources/\0common.res"));
+}
+
+#[cfg(unix)]
+#[test]
+fn paths_are_checked_once_resolved() {
+  // A link without a comma to a directory with one.
+  let dir = temp_dir("links");
+  let target = dir.join("c,d");
+  fs::create_dir_all(&target).unwrap();
+  fs::copy(MODEL, target.join("snowboy.umdl")).unwrap();
+  std::os::unix::fs::symlink(&target, dir.join("link")).unwrap();
+  let result = SnowboyDetect::new(RESOURCE, &[dir.join("link/snowboy.umdl")]);
+  fs::remove_dir_all(&dir).unwrap();
+  assert_eq!(invalid_path(result), target.join("snowboy.umdl"));
+}
+
+#[test]
+fn relative_paths_are_resolved() {
+  let detector = SnowboyDetect::new("resources/models/../common.res", &[Path::new("./resources/models/snowboy.umdl")]);
+  assert_eq!(detector.unwrap().num_hotwords(), 1);
+
+  // Errors name the absolute path of the file.
+  match SnowboyDetect::new(RESOURCE, &["resources/models/../ding.wav"]) {
+    Err(SnowboyError::InvalidModel { path, .. }) => {
+      assert!(path.is_absolute());
+      assert_eq!(path, fs::canonicalize("resources/ding.wav").unwrap());
+    }
+    other => panic!("unexpected {:?}", other),
+  }
+}
@@ -0,0 +1,104 @@
+use std::thread;
+
+use rsnowboy::preroll::PreRoll;
+use rsnowboy::SnowboyError;
+
+/// Frames of `channels` samples, each sample holding the offset of its frame.
+fn frames(start: i32, count: i32, channels: usize) -> Vec<i32> {
+  (start..start + count).flat_map(|frame| vec![frame; channels]).collect()
+}
+
+#[test]
+fn old_frames_are_overwritten() {
+  // 10 frames of 2 channels.
+  let mut pre_roll = PreRoll::new(10, 1000, 2).unwrap();
+  assert_eq!(pre_roll.capacity_frames(), 10);
+  assert!(pre_roll.is_empty());
+
+  pre_roll.push(&frames(0, 6, 2)).unwrap();
+  assert_eq!((pre_roll.len_frames(), pre_roll.sample_offset(), pre_roll.end_offset()), (6, 0, 6));
+  // Wraps around the end of the buffer.
+  pre_roll.push(&frames(6, 7, 2)).unwrap();
+  assert_eq!((pre_roll.len_frames(), pre_roll.sample_offset(), pre_roll.end_offset()), (10, 3, 13));
+  let snapshot = pre_roll.snapshot();
+  assert_eq!(snapshot.samples, frames(3, 10, 2));
+  assert_eq!((snapshot.sample_offset, snapshot.end_offset), (3, 13));
+
+  // Small pushes go round several times.
+  for frame in 13..40 {
+    pre_roll.push(&frames(frame, 1, 2)).unwrap();
+    let snapshot = pre_roll.snapshot();
+    assert_eq!(snapshot.samples, frames(snapshot.sample_offset as i32, frame + 1 - snapshot.sample_offset as i32, 2));
+    assert_eq!((snapshot.sample_offset, snapshot.end_offset), (frame as u64 - 9, frame as u64 + 1));
+  }
+}
+
+#[test]
+fn pushes_larger_than_the_buffer_keep_their_end() {
+  let mut pre_roll = PreRoll::new(10, 1000, 1).unwrap();
+  pre_roll.push(&frames(0, 3, 1)).unwrap();
+  pre_roll.push(&frames(3, 25, 1)).unwrap();
+  let snapshot = pre_roll.snapshot();
+  assert_eq!(snapshot.samples, frames(18, 10, 1));
+  assert_eq!((snapshot.sample_offset, snapshot.end_offset), (18, 28));
+
+  // From an empty buffer, and by exactly the capacity.
+  let mut pre_roll = PreRoll::new(10, 1000, 1).unwrap();
+  pre_roll.push(&frames(0, 17, 1)).unwrap();
+  assert_eq!(pre_roll.snapshot().samples, frames(7, 10, 1));
+  pre_roll.push(&frames(17, 10, 1)).unwrap();
+  assert_eq!(pre_roll.snapshot().samples, frames(17, 10, 1));
+
+  assert!(matches!(PreRoll::new(10, 1000, 2).unwrap().push(&[0; 3]), Err(SnowboyError::PartialFrame { .. })));
+  assert!(matches!(PreRoll::<i16>::new(0, 16000, 1), Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn snapshots_end_with_the_trigger_chunk() {
+  // 2 s at 16 kHz, in chunks of 100 ms, the 35th of which has the hotword.
+  let mut pre_roll = PreRoll::new(2000, 16000, 1).unwrap();
+  for chunk in 0..35 {
+    pre_roll.push(&frames(chunk * 1600, 1600, 1)).unwrap();
+  }
+  let snapshot = pre_roll.snapshot();
+  assert_eq!((snapshot.sample_offset, snapshot.end_offset), (35 * 1600 - 32000, 35 * 1600));
+  assert_eq!(snapshot.samples[snapshot.samples.len() - 1600..], frames(34 * 1600, 1600, 1)[..]);
+  assert_eq!(snapshot.samples[0], snapshot.sample_offset as i32);
+
+  // Clearing keeps the offsets, and the next snapshot starts after it.
+  pre_roll.clear();
+  assert!(pre_roll.snapshot().samples.is_empty());
+  pre_roll.push(&frames(35 * 1600, 1600, 1)).unwrap();
+  let snapshot = pre_roll.snapshot();
+  assert_eq!((snapshot.sample_offset, snapshot.end_offset), (35 * 1600, 36 * 1600));
+
+  pre_roll.reset();
+  assert_eq!((pre_roll.sample_offset(), pre_roll.end_offset()), (0, 0));
+  pre_roll.push(&[7; 4]).unwrap();
+  assert_eq!(pre_roll.snapshot().samples, vec![7; 4]);
+}
+
+#[test]
+fn the_consumer_only_sees_whole_pushes() {
+  let (mut producer, consumer) = PreRoll::new(10, 1000, 2).unwrap().split();
+  let writer = thread::spawn(move || {
+    for chunk in 0..20_000 {
+      producer.push(&frames(chunk * 3, 3, 2)).unwrap();
+    }
+    producer.end_offset()
+  });
+
+  let mut last_end = 0;
+  while !writer.is_finished() {
+    let snapshot = consumer.snapshot();
+    // Frames that were overwritten during the copy are left out, never torn.
+    let expected = frames(snapshot.sample_offset as i32, (snapshot.end_offset - snapshot.sample_offset) as i32, 2);
+    assert_eq!(snapshot.samples, expected);
+    assert!(snapshot.end_offset - snapshot.sample_offset <= 10);
+    assert!(snapshot.end_offset >= last_end);
+    last_end = snapshot.end_offset;
+  }
+  assert_eq!(writer.join().unwrap(), 60_000);
+  assert_eq!(consumer.snapshot().samples, frames(59_990, 10, 2));
+  assert_eq!((consumer.sample_offset(), consumer.end_offset()), (59_990, 60_000));
+}
@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+
+use rsnowboy::resample::{Quality, Resampler};
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyError};
+
+fn tone(rate: u32, frequency: f64, frames: usize) -> Vec<f32> {
+  (0..frames).map(|i| (2.0 * PI * frequency * i as f64 / f64::from(rate)).sin() as f32).collect()
+}
+
+fn resample(input_rate: u32, output_rate: u32, quality: Quality, input: &[f32]) -> Vec<f32> {
+  let mut resampler = Resampler::new(input_rate, output_rate, 1, quality).unwrap();
+  let mut output = Vec::new();
+  resampler.process(input, &mut output);
+  resampler.flush(&mut output);
+  output
+}
+
+/// Returns the amplitude of the `frequency` component of `samples`, leaving
+/// out the filter transients at both ends.
+fn amplitude(rate: u32, frequency: f64, samples: &[f32]) -> f64 {
+  let samples = &samples[samples.len() / 10..samples.len() * 9 / 10];
+  let (mut sin, mut cos) = (0.0, 0.0);
+  for (i, &sample) in samples.iter().enumerate() {
+    let angle = 2.0 * PI * frequency * i as f64 / f64::from(rate);
+    sin += f64::from(sample) * angle.sin();
+    cos += f64::from(sample) * angle.cos();
+  }
+  2.0 * (sin * sin + cos * cos).sqrt() / samples.len() as f64
+}
+
+fn decibels(amplitude: f64) -> f64 {
+  20.0 * amplitude.log10()
+}
+
+#[test]
+fn passband_is_flat() {
+  for &input_rate in &[48000, 44100, 22050, 8000, 47999] {
+    for &frequency in &[100.0, 440.0, 1000.0, 2500.0, 3500.0] {
+      let input = tone(input_rate, frequency, input_rate as usize);
+      let output = resample(input_rate, 16000, Quality::High, &input);
+      let gain = decibels(amplitude(16000, frequency, &output));
+      assert!(gain.abs() < 0.05, "{} Hz tone at {} Hz: {} dB", frequency, input_rate, gain);
+    }
+  }
+  let input = tone(48000, 6000.0, 48000);
+  let gain = decibels(amplitude(16000, 6000.0, &resample(48000, 16000, Quality::High, &input)));
+  assert!(gain.abs() < 0.1, "6000 Hz tone: {} dB", gain);
+}
+
+#[test]
+fn frequencies_above_the_output_nyquist_are_removed() {
+  let cases = [(Quality::Fast, 40.0), (Quality::Medium, 60.0), (Quality::High, 80.0)];
+  for &(quality, attenuation) in &cases {
+    for &frequency in &[10000.0, 12000.0, 15000.0] {
+      let input = tone(48000, frequency, 48000);
+      let output = resample(48000, 16000, quality, &input);
+      // A tone at f folds to 16 kHz - f at the output rate.
+      let aliased = decibels(amplitude(16000, 16000.0 - frequency, &output));
+      let power = decibels((output[1600..14400].iter().map(|&s| f64::from(s) * f64::from(s)).sum::<f64>()
+                            / 12800.0 * 2.0).sqrt());
+      assert!(aliased < -attenuation, "{:?}, {} Hz: alias at {} dB", quality, frequency, aliased);
+      assert!(power < -attenuation, "{:?}, {} Hz: {} dB left", quality, frequency, power);
+    }
+  }
+}
+
+#[test]
+fn chunks_are_converted_as_a_single_stream() {
+  let input = tone(44100, 1234.0, 44100);
+  let expected = resample(44100, 16000, Quality::Medium, &input);
+
+  let mut resampler = Resampler::new(44100, 16000, 1, Quality::Medium).unwrap();
+  let mut output = Vec::new();
+  let mut rest = &input[..];
+  for size in [1, 7, 441, 1000, 3].iter().cycle() {
+    if rest.is_empty() {
+      break;
+    }
+    let (chunk, next) = rest.split_at((*size).min(rest.len()));
+    resampler.process(chunk, &mut output);
+    rest = next;
+  }
+  resampler.flush(&mut output);
+  assert_eq!(output, expected);
+}
+
+#[test]
+fn flush_returns_the_whole_stream() {
+  for &(input_rate, output_rate, frames, expected) in
+    &[(48000, 16000, 4800, 1600), (44100, 16000, 44100, 16000), (44100, 16000, 100, 37), (8000, 16000, 5, 10)] {
+    let output = resample(input_rate, output_rate, Quality::Fast, &vec![0.0; frames]);
+    assert_eq!(output.len(), expected, "{} frames from {} Hz to {} Hz", frames, input_rate, output_rate);
+  }
+
+  // The resampler is ready for another stream after a flush.
+  let mut resampler = Resampler::new(48000, 16000, 1, Quality::Fast).unwrap();
+  let input = tone(48000, 300.0, 4800);
+  let mut first = Vec::new();
+  resampler.process(&input, &mut first);
+  resampler.flush(&mut first);
+  let mut second = Vec::new();
+  resampler.process(&input, &mut second);
+  resampler.flush(&mut second);
+  assert_eq!(first, second);
+}
+
+#[test]
+fn channels_are_converted_separately() {
+  let left = tone(48000, 500.0, 4800);
+  let right = tone(48000, 2000.0, 4800);
+  let input = left.iter().zip(&right).flat_map(|(&l, &r)| vec![l, r]).collect::<Vec<f32>>();
+
+  let mut resampler =
+    Resampler::for_format(48000, AudioFormat::new(16000, 2, 16, SampleFormat::Int), Quality::Medium).unwrap();
+  assert_eq!(resampler.channels(), 2);
+  let mut output = Vec::new();
+  resampler.process(&input, &mut output);
+  resampler.flush(&mut output);
+  let left_output = output.iter().step_by(2).copied().collect::<Vec<f32>>();
+  let right_output = output.iter().skip(1).step_by(2).copied().collect::<Vec<f32>>();
+  assert_eq!(left_output, resample(48000, 16000, Quality::Medium, &left));
+  assert_eq!(right_output, resample(48000, 16000, Quality::Medium, &right));
+}
+
+#[test]
+fn i16_samples_are_rounded_and_clipped() {
+  // The edges of a full scale square wave overshoot once filtered.
+  let square = (0..4800).map(|i| if i / 240 % 2 == 0 { i16::MAX } else { i16::MIN }).collect::<Vec<i16>>();
+  let mut resampler = Resampler::new(48000, 44100, 1, Quality::Fast).unwrap();
+  let mut output = Vec::new();
+  resampler.process_i16(&square, &mut output);
+  resampler.flush_i16(&mut output);
+  assert_eq!(output.len(), 4410);
+  assert!(output.contains(&i16::MAX) && output.contains(&i16::MIN));
+
+  let constant = vec![1000i16; 4800];
+  let mut output = Vec::new();
+  resampler.process_i16(&constant, &mut output);
+  resampler.flush_i16(&mut output);
+  let middle = &output[500..output.len() - 500];
+  assert!(middle.iter().all(|&sample| sample == 1000), "{:?}", middle);
+}
+
+#[test]
+fn rejects_empty_rates_and_channels() {
+  assert!(matches!(Resampler::new(0, 16000, 1, Quality::High), Err(SnowboyError::InvalidArgument(_))));
+  assert!(matches!(Resampler::new(48000, 16000, 0, Quality::High), Err(SnowboyError::InvalidArgument(_))));
+}
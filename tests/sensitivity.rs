@@ -0,0 +1,87 @@
+use std::num::NonZeroU32;
+
+use rsnowboy::{Sensitivities, SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn index(index: u32) -> NonZeroU32 {
+  NonZeroU32::new(index).unwrap()
+}
+
+/// A detector with the model loaded twice, so with two hotwords.
+fn detector() -> SnowboyDetect {
+  let detector = SnowboyDetect::new(RESOURCE, &[MODEL, MODEL]).unwrap();
+  assert_eq!(detector.num_hotwords(), 2);
+  detector
+}
+
+#[test]
+fn sensitivities_are_checked() {
+  assert_eq!("0.4, 0.5".parse::<Sensitivities>().unwrap().as_slice(), &[0.4, 0.5]);
+  assert!(matches!(Sensitivities::new(vec![0.5, 1.5]), Err(SnowboyError::InvalidSensitivity(value)) if value == 1.5));
+  assert!(matches!(Sensitivities::uniform(-0.1, 2), Err(SnowboyError::InvalidSensitivity(_))));
+  assert!(matches!(Sensitivities::new(vec![f32::NAN]), Err(SnowboyError::InvalidSensitivity(_))));
+  assert!(matches!("0.5,high".parse::<Sensitivities>(), Err(SnowboyError::InvalidArgument(_))));
+
+  let mut sensitivities = Sensitivities::uniform(0.5, 2).unwrap();
+  sensitivities.set_hotword(index(2), 0.8).unwrap();
+  assert_eq!((sensitivities.hotword(index(1)), sensitivities.hotword(index(2))), (Some(0.5), Some(0.8)));
+  assert_eq!(sensitivities.hotword(index(3)), None);
+  let result = sensitivities.set_hotword(index(3), 0.8);
+  assert!(matches!(result, Err(SnowboyError::HotwordIndex { num_hotwords: 2, .. })));
+  assert!(matches!(sensitivities.set_hotword(index(1), 2.0), Err(SnowboyError::InvalidSensitivity(_))));
+  assert_eq!(sensitivities.to_string(), "0.5,0.8");
+}
+
+#[test]
+fn detectors_take_one_sensitivity_per_hotword() {
+  let mut detector = detector();
+  detector.set_sensitivity(vec![0.4, 0.6]).unwrap();
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.4, 0.6]);
+
+  for values in [vec![0.5], vec![0.5, 0.5, 0.5]] {
+    let found = values.len();
+    let result = detector.set_sensitivity(values);
+    assert!(matches!(result, Err(SnowboyError::SensitivityCount { expected: 2, found: f }) if f == found));
+  }
+  assert!(matches!(detector.set_sensitivity(vec![0.5, 1.5]), Err(SnowboyError::InvalidSensitivity(_))));
+  // Errors keep the previous values.
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.4, 0.6]);
+}
+
+#[test]
+fn hotwords_are_set_by_index() {
+  let mut detector = detector();
+  detector.set_sensitivity("0.4,0.6".parse::<Sensitivities>().unwrap()).unwrap();
+  detector.set_hotword_sensitivity(index(2), 0.9).unwrap();
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.4, 0.9]);
+  detector.set_hotword_sensitivity(index(1), 0.1).unwrap();
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.1, 0.9]);
+
+  let result = detector.set_hotword_sensitivity(index(3), 0.5);
+  assert!(matches!(result, Err(SnowboyError::HotwordIndex { num_hotwords: 2, .. })));
+  assert!(matches!(detector.set_hotword_sensitivity(index(1), 1.1), Err(SnowboyError::InvalidSensitivity(_))));
+  assert_eq!(detector.get_sensitivity().unwrap().as_slice(), &[0.1, 0.9]);
+}
+
+#[test]
+fn high_sensitivities_are_remembered() {
+  let mut detector = detector();
+  assert_eq!(detector.high_sensitivity(), None);
+  detector.set_high_sensitivity(vec![0.8, 0.9]).unwrap();
+  assert_eq!(detector.high_sensitivity().unwrap().as_slice(), &[0.8, 0.9]);
+  // They are separate from the sensitivities.
+  assert_ne!(detector.get_sensitivity().unwrap().as_slice(), &[0.8, 0.9]);
+
+  // Rejected values keep the last ones.
+  let result = detector.set_high_sensitivity(vec![0.8]);
+  assert!(matches!(result, Err(SnowboyError::SensitivityCount { expected: 2, found: 1 })));
+  assert!(matches!(detector.set_high_sensitivity(vec![0.8, 1.2]), Err(SnowboyError::InvalidSensitivity(_))));
+  assert_eq!(detector.high_sensitivity().unwrap().as_slice(), &[0.8, 0.9]);
+
+  // Detection still runs with them.
+  detector.set_high_sensitivity(Sensitivities::uniform(1.0, 2).unwrap()).unwrap();
+  assert!(detector.detect(&[0i16; 1600], false).is_ok());
+  assert!(SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap().high_sensitivity().is_none());
+}
@@ -0,0 +1,86 @@
+use std::io::{self, Read, Write};
+use std::sync::mpsc;
+
+use rsnowboy::events::EventKind;
+use rsnowboy::source::{AudioSource, Generator};
+use rsnowboy::{SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+/// Returns at most `step` bytes at a time, like a pipe.
+struct Trickle<R> {
+  reader: R,
+  step: usize,
+}
+
+impl<R: Read> Read for Trickle<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let len = buf.len().min(self.step);
+    self.reader.read(&mut buf[..len])
+  }
+}
+
+/// 1 s of silence, then 1.05 s of sound.
+fn speech(detector: &SnowboyDetect) -> Generator {
+  Generator::new(detector.format(), Some(32800), |frame, _| {
+    if frame >= 16000 { 0.3 * (frame as f32 * 0.3).sin() } else { 0.0 }
+  })
+}
+
+#[test]
+fn writes_may_split_samples() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let mut data = vec![0; 32800 * 2];
+  assert_eq!(speech(&detector).read_chunk(&mut data).unwrap(), data.len());
+  let expected = detector.events(speech(&detector)).unwrap().map(|event| event.unwrap().kind).collect::<Vec<_>>();
+  assert!(expected.contains(&EventKind::Sound));
+
+  for &step in &[1, 333, 4096] {
+    let mut events = Vec::new();
+    let mut sink = detector.sink(|event| events.push(event.unwrap())).unwrap();
+    io::copy(&mut Trickle { reader: &data[..], step }, &mut sink).unwrap();
+    assert_eq!(sink.sample_offset(), 32000);
+    sink.finish().unwrap();
+    drop(sink);
+
+    assert_eq!(events.iter().map(|event| event.kind).collect::<Vec<_>>(), expected, "writes of {} bytes", step);
+    assert_eq!(events.iter().map(|event| event.sample_offset).collect::<Vec<_>>(),
+               (0..21).map(|i| i * 1600).collect::<Vec<_>>());
+  }
+}
+
+#[test]
+fn errors_are_sent_and_returned() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let (sender, receiver) = mpsc::channel();
+  let mut sink = detector.sink_channel(sender).unwrap();
+  sink.write_all(&[0; 6401]).unwrap();
+  assert!(sink.finish().is_err());
+  drop(sink);
+
+  let events = receiver.try_iter().collect::<Vec<_>>();
+  assert_eq!(events.len(), 3);
+  assert!(events[..2].iter().all(|event| matches!(event, Ok(event) if event.kind == EventKind::Silence)));
+  assert!(matches!(events[2], Err(SnowboyError::PartialFrame { .. })));
+}
+
+#[test]
+fn streams_ending_on_a_chunk_boundary_have_no_empty_event() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let (sender, receiver) = mpsc::channel();
+  let mut sink = detector.sink_channel(sender).unwrap();
+  sink.write_all(&[0; 3200 * 2]).unwrap();
+  sink.finish().unwrap();
+  // Nor does a stream with nothing written.
+  sink.finish().unwrap();
+  drop(sink);
+
+  let events = receiver.try_iter().map(|event| event.unwrap()).collect::<Vec<_>>();
+  assert_eq!(events.iter().map(|event| event.sample_offset).collect::<Vec<_>>(), vec![0, 1600]);
+  assert!(events.iter().all(|event| event.kind == EventKind::Silence));
+
+  // The same events as a source.
+  let silence = Generator::silence(detector.format(), 200);
+  assert_eq!(detector.events(silence).unwrap().count(), 2);
+}
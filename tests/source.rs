@@ -0,0 +1,194 @@
+use std::io::{self, Cursor, Read};
+use std::time::{Duration, Instant};
+
+use rsnowboy::source::{AudioSource, Generator, RawSource};
+use rsnowboy::wav::WavReader;
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyDetect, SnowboyError, SnowboyVad, VadResult};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+/// Returns at most `step` bytes at a time, like a pipe.
+struct Trickle<R> {
+  reader: R,
+  step: usize,
+}
+
+impl<R: Read> Read for Trickle<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let len = buf.len().min(self.step);
+    self.reader.read(&mut buf[..len])
+  }
+}
+
+fn mono_16k() -> AudioFormat {
+  AudioFormat::new(16000, 1, 16, SampleFormat::Int)
+}
+
+fn read_all<A: AudioSource>(source: &mut A, chunk_size: usize) -> Vec<u8> {
+  let mut data = Vec::new();
+  let mut buffer = vec![0; chunk_size];
+  loop {
+    let read = source.read_chunk(&mut buffer).unwrap();
+    if read == 0 {
+      return data;
+    }
+    assert_eq!(read % source.format().bytes_per_frame(), 0);
+    data.extend_from_slice(&buffer[..read]);
+  }
+}
+
+#[test]
+fn wav_files_are_sources() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let mut reader = WavReader::open("resources/ding.wav").unwrap();
+  let offsets = detector.detect_source(&mut reader).unwrap().iter()
+    .map(|detection| detection.sample_offset)
+    .collect::<Vec<_>>();
+  assert_eq!(offsets, vec![0, 1600, 3200, 4800, 6400]);
+}
+
+#[test]
+fn raw_sources_return_whole_frames() {
+  let format = AudioFormat::new(16000, 2, 24, SampleFormat::Int);
+  let bytes = (0..6 * 100 + 4).map(|i| i as u8).collect::<Vec<u8>>();
+  for &step in &[1, 5, 7, 1000] {
+    let mut source = RawSource::new(Trickle { reader: Cursor::new(bytes.clone()), step }, format);
+    // The last 4 bytes are not a whole frame.
+    assert_eq!(read_all(&mut source, 64), &bytes[..600], "reads of {} bytes", step);
+  }
+
+  let mut source = RawSource::new(Cursor::new(bytes), format);
+  assert!(matches!(source.read_chunk(&mut [0; 5]), Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn generators_encode_samples_in_their_format() {
+  let data = read_all(&mut Generator::sine(mono_16k(), 1000.0, 0.5, 100), 1000);
+  let samples = data.chunks(2).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])).collect::<Vec<i16>>();
+  assert_eq!(samples.len(), 1600);
+  assert_eq!(samples[0], 0);
+  assert_eq!(samples[4], 16384);
+  assert_eq!(samples[12], -16384);
+
+  let stereo = AudioFormat::new(8000, 2, 8, SampleFormat::Int);
+  let data = read_all(&mut Generator::silence(stereo, 250), 999);
+  assert_eq!(data, vec![128; 4000]);
+
+  let mut endless = Generator::new(mono_16k(), None, |frame, _| if frame % 2 == 0 { 1.0 } else { -1.0 });
+  let mut buffer = [0; 4];
+  for _ in 0..10 {
+    assert_eq!(endless.read_chunk(&mut buffer).unwrap(), 4);
+    assert_eq!(buffer, [0xff, 0x7f, 0x00, 0x80]);
+  }
+}
+
+#[test]
+fn detect_source_checks_the_format() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let mut source = Generator::silence(AudioFormat::new(8000, 1, 16, SampleFormat::Int), 1000);
+  assert!(matches!(detector.detect_source(&mut source), Err(SnowboyError::FormatMismatch { .. })));
+
+  let mut vad = SnowboyVad::new(RESOURCE).unwrap();
+  let detections = vad.detect_source(&mut Generator::silence(mono_16k(), 1050)).unwrap();
+  assert_eq!(detections.len(), 11);
+  assert_eq!(detections[10].sample_offset, 16000);
+  assert!(detections.iter().all(|detection| detection.result == VadResult::Silence));
+}
+
+#[test]
+fn every_reader_ends_streams_the_same_way() {
+  use rsnowboy::chunker::Chunker;
+  use rsnowboy::events::EventKind;
+  use rsnowboy::DetectionResult;
+
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let format = detector.format();
+  let sound = |millis: u64| Generator::new(format, Some(millis * 16), |frame, _| (frame as f32 * 0.2).sin() * 0.5);
+  // On a chunk boundary, and in the middle of a chunk.
+  for &millis in &[1000, 1050] {
+    let from_source = detector.detect_source(&mut sound(millis)).unwrap().into_iter()
+      .map(|detection| (detection.sample_offset, detection.result))
+      .collect::<Vec<_>>();
+    assert_eq!(from_source.len(), if millis == 1000 { 10 } else { 11 });
+
+    detector.reset().unwrap();
+    let from_events = detector.events(sound(millis)).unwrap()
+      .map(|event| event.unwrap())
+      .map(|event| (event.sample_offset, event.kind))
+      .collect::<Vec<_>>();
+    let kinds = from_source.iter()
+      .map(|(offset, result)| (*offset, match result {
+        DetectionResult::Silence => EventKind::Silence,
+        DetectionResult::Sound => EventKind::Sound,
+        DetectionResult::Hotword { .. } => EventKind::Hotword,
+      }))
+      .collect::<Vec<_>>();
+    assert_eq!(from_events, kinds, "{} ms", millis);
+
+    detector.reset().unwrap();
+    let mut chunker = Chunker::for_format(100, format).unwrap();
+    let bytes = read_all(&mut sound(millis), 3200);
+    let mut from_chunker = chunker.push(&bytes, |chunk, is_end| detector.detect_bytes(chunk, is_end)).unwrap();
+    from_chunker.push(chunker.finish(|chunk, is_end| detector.detect_bytes(chunk, is_end)).unwrap());
+    let from_chunker = from_chunker.into_iter()
+      .map(|detection| (detection.sample_offset, detection.result))
+      .collect::<Vec<_>>();
+    // Which also returns the empty chunk at the end.
+    assert_eq!(from_chunker[..from_source.len()], from_source[..], "{} ms", millis);
+    assert_eq!(from_chunker.len(), 11);
+    detector.reset().unwrap();
+  }
+}
+
+#[cfg(unix)]
+#[test]
+fn commands_are_sources() {
+  use std::process::Command;
+
+  use rsnowboy::source::CommandSource;
+
+  let mut command = Command::new("sh");
+  command.args(["-c", "head -c 6401 /dev/zero"]);
+  let mut source = CommandSource::spawn(command, mono_16k()).unwrap();
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let offsets = detector.detect_source(&mut source).unwrap().iter()
+    .map(|detection| detection.sample_offset)
+    .collect::<Vec<_>>();
+  assert_eq!(offsets, vec![0, 1600]);
+
+  let mut command = Command::new("sh");
+  command.args(["-c", "printf abcd; exit 3"]);
+  let mut source = CommandSource::spawn(command, mono_16k()).unwrap();
+  let mut buffer = [0; 100];
+  assert_eq!(source.read_chunk(&mut buffer).unwrap(), 4);
+  match source.read_chunk(&mut buffer) {
+    Err(SnowboyError::Io(error)) => assert!(error.to_string().contains("exit status: 3"), "{}", error),
+    other => panic!("expected an error, got {:?}", other),
+  }
+
+  // Killed commands end the stream without an error.
+  let mut command = Command::new("sleep");
+  command.arg("30");
+  let mut source = CommandSource::spawn(command, mono_16k()).unwrap();
+  let kill = source.kill_handle();
+  kill.kill().unwrap();
+  assert_eq!(source.read_chunk(&mut buffer).unwrap(), 0);
+  drop(source);
+  assert!(kill.kill().is_ok());
+
+  // Nor do they wait for a command that closed its output but keeps running.
+  let mut command = Command::new("sh");
+  command.args(["-c", "exec >&-; sleep 30"]);
+  let mut source = CommandSource::spawn(command, mono_16k()).unwrap();
+  let kill = source.kill_handle();
+  let reader = std::thread::spawn(move || source.read_chunk(&mut [0; 100]).map_err(|error| error.to_string()));
+  std::thread::sleep(Duration::from_millis(100));
+  let start = Instant::now();
+  kill.kill().unwrap();
+  assert!(start.elapsed() < Duration::from_secs(1));
+  assert_eq!(reader.join().unwrap(), Ok(0));
+
+  let missing = CommandSource::spawn(Command::new("rsnowboy-no-such-command"), mono_16k());
+  assert!(matches!(missing, Err(SnowboyError::Io(ref error)) if error.kind() == io::ErrorKind::NotFound));
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "async")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::executor::{block_on, block_on_stream};
+use futures::{stream, StreamExt};
+
+use rsnowboy::events::EventKind;
+use rsnowboy::source::{AudioSource, Generator};
+use rsnowboy::{SnowboyDetect, SnowboyError};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn detector() -> SnowboyDetect {
+  SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap()
+}
+
+/// Waits up to a second for `condition`.
+fn eventually(condition: impl Fn() -> bool) -> bool {
+  let start = Instant::now();
+  while start.elapsed() < Duration::from_secs(1) {
+    if condition() {
+      return true;
+    }
+    thread::sleep(Duration::from_millis(10));
+  }
+  false
+}
+
+#[test]
+fn streams_of_chunks_give_events() {
+  let mut detector = detector();
+  let speech = |format| Generator::new(format, Some(32800), |frame, _| {
+    if frame >= 16000 { 0.3 * (frame as f32 * 0.3).sin() } else { 0.0 }
+  });
+  let mut data = vec![0; 32800 * 2];
+  speech(detector.format()).read_chunk(&mut data).unwrap();
+  let expected = detector.events(speech(detector.format())).unwrap()
+    .map(|event| event.map(|event| (event.kind, event.sample_offset)))
+    .collect::<Result<Vec<_>, _>>()
+    .unwrap();
+
+  let chunks = data.chunks(777).map(|chunk| chunk.to_vec()).collect::<Vec<_>>();
+  let events = detector.into_stream(stream::iter(chunks), 4).unwrap()
+    .map(|event| event.map(|event| (event.kind, event.sample_offset)));
+  let events = block_on(events.collect::<Vec<_>>()).into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+  assert_eq!(events, expected);
+  assert!(events.iter().any(|&(kind, _)| kind == EventKind::Sound));
+}
+
+#[test]
+fn errors_end_the_stream() {
+  let events = detector().into_stream(stream::iter(vec![vec![0u8; 3200], vec![0u8; 3]]), 4).unwrap();
+  let events = block_on_stream(events).collect::<Vec<_>>();
+  assert_eq!(events.len(), 2);
+  assert!(matches!(events[1], Err(SnowboyError::PartialFrame { .. })));
+
+  let empty = stream::empty::<Vec<u8>>();
+  assert!(matches!(detector().into_stream(empty, 0), Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn full_streams_stop_reading_chunks() {
+  let pulled = Arc::new(AtomicUsize::new(0));
+  let counter = pulled.clone();
+  let chunks = stream::repeat(vec![0u8; 3200]).inspect(move |_| {
+    counter.fetch_add(1, Ordering::SeqCst);
+  });
+  let mut events = detector().into_stream(chunks, 2).unwrap();
+  assert!(eventually(|| pulled.load(Ordering::SeqCst) >= 2));
+  thread::sleep(Duration::from_millis(200));
+  // Both events wait in the stream.
+  assert_eq!(pulled.load(Ordering::SeqCst), 2);
+
+  let event = block_on(events.next()).unwrap().unwrap();
+  assert_eq!((event.kind, event.sample_offset), (EventKind::Silence, 0));
+  assert!(eventually(|| pulled.load(Ordering::SeqCst) == 3));
+}
+
+#[test]
+fn dropping_the_stream_stops_detection() {
+  // Holds a reference as long as the detection thread has the chunks.
+  let guard = Arc::new(());
+  let held = guard.clone();
+  let chunks = stream::once(async { vec![0u8; 3200] })
+    .chain(stream::pending())
+    .inspect(move |_| {
+      let _ = &held;
+    });
+  let mut events = detector().into_stream(chunks, 4).unwrap();
+  let event = block_on(events.next()).unwrap().unwrap();
+  assert_eq!(event.kind, EventKind::Silence);
+  assert_eq!(Arc::strong_count(&guard), 2);
+
+  drop(events);
+  assert!(eventually(|| Arc::strong_count(&guard) == 1));
+}
@@ -0,0 +1,170 @@
+use std::num::NonZeroU32;
+
+use rsnowboy::utterance::{EndReason, Utterance, UtteranceRecorder};
+use rsnowboy::{DetectionResult, SnowboyError, VadResult};
+
+/// 100 ms at 16 kHz, every sample holding the number of the chunk.
+fn chunk(number: usize) -> Vec<i16> {
+  vec![number as i16; 1600]
+}
+
+fn chunks(numbers: std::ops::RangeInclusive<usize>) -> Vec<i16> {
+  numbers.flat_map(chunk).collect()
+}
+
+fn hotword() -> DetectionResult {
+  DetectionResult::Hotword { index: NonZeroU32::new(1).unwrap(), name: Some("snowboy".to_string()) }
+}
+
+/// 300 ms of pre-roll, 300 ms of speech and 500 ms of silence to end, 1 s of
+/// initial timeout and 3 s at most.
+fn recorder() -> UtteranceRecorder<i16> {
+  UtteranceRecorder::new(16000, 1).unwrap()
+    .pre_roll(300).unwrap()
+    .min_speech(300)
+    .trailing_silence(500)
+    .initial_timeout(1000)
+    .max_length(3000)
+}
+
+/// Pushes chunk `i` with `results[i]`, and returns the utterances with the
+/// number of the chunk that ended them.
+fn run(recorder: &mut UtteranceRecorder<i16>, results: &[DetectionResult]) -> Vec<(usize, Utterance<i16>)> {
+  let mut utterances = Vec::new();
+  for (number, result) in results.iter().enumerate() {
+    if let Some(utterance) = recorder.push(&chunk(number), result).unwrap() {
+      utterances.push((number, utterance));
+    }
+  }
+  utterances
+}
+
+fn script(parts: &[(DetectionResult, usize)]) -> Vec<DetectionResult> {
+  parts.iter().flat_map(|(result, count)| vec![result.clone(); *count]).collect()
+}
+
+#[test]
+fn utterances_end_after_trailing_silence() {
+  use DetectionResult::{Silence, Sound};
+  let mut recorder = recorder();
+  let results = script(&[(Silence, 4), (hotword(), 1), (Sound, 4), (Silence, 8)]);
+  let mut utterances = run(&mut recorder, &results);
+  assert_eq!(utterances.len(), 1);
+  let (ended, utterance) = utterances.remove(0);
+  assert_eq!(ended, 13);
+  assert_eq!(utterance.end_reason, EndReason::Silence);
+  assert_eq!((utterance.hotword.get(), utterance.hotword_name.as_deref()), (1, Some("snowboy")));
+  // 300 ms before the end of the chunk with the hotword, up to the end of the
+  // last chunk, each chunk once.
+  assert_eq!((utterance.start, utterance.end), (5 * 1600 - 4800, 14 * 1600));
+  assert_eq!(utterance.audio, chunks(2..=13));
+  assert!(!recorder.is_recording());
+}
+
+#[test]
+fn short_sounds_are_not_speech() {
+  use DetectionResult::{Silence, Sound};
+  // 200 ms of sound, then silence: only the initial timeout ends it.
+  let mut utterances = run(&mut recorder(), &script(&[(hotword(), 1), (Sound, 2), (Silence, 12)]));
+  assert_eq!(utterances.len(), 1);
+  let (ended, utterance) = utterances.remove(0);
+  assert_eq!((ended, utterance.end_reason), (10, EndReason::Timeout));
+  assert_eq!(utterance.audio, chunks(0..=10));
+
+  // Sound is added up until there is enough of it.
+  let results = script(&[(hotword(), 1), (Sound, 2), (Silence, 1), (Sound, 1), (Silence, 5)]);
+  let utterances = run(&mut recorder(), &results);
+  assert_eq!(utterances.iter().map(|(ended, utterance)| (*ended, utterance.end_reason)).collect::<Vec<_>>(),
+             vec![(9, EndReason::Silence)]);
+}
+
+#[test]
+fn nobody_speaking_times_out() {
+  let mut recorder = recorder();
+  let mut utterances = run(&mut recorder, &script(&[(hotword(), 1), (DetectionResult::Silence, 15)]));
+  assert_eq!(utterances.len(), 1);
+  let (ended, utterance) = utterances.remove(0);
+  assert_eq!((ended, utterance.end_reason), (10, EndReason::Timeout));
+  assert_eq!((utterance.start, utterance.end), (0, 11 * 1600));
+  assert!(!recorder.is_recording());
+}
+
+#[test]
+fn utterances_stop_at_max_length() {
+  let results = script(&[(hotword(), 1), (DetectionResult::Sound, 40)]);
+  let mut utterances = run(&mut recorder(), &results);
+  assert_eq!(utterances.len(), 1);
+  let (ended, utterance) = utterances.remove(0);
+  assert_eq!((ended, utterance.end_reason), (30, EndReason::MaxLength));
+  assert_eq!(utterance.audio, chunks(0..=30));
+  assert_eq!((utterance.start, utterance.end), (0, 31 * 1600));
+
+  // The stream may end first.
+  let mut recorder = recorder();
+  assert!(run(&mut recorder, &script(&[(hotword(), 1), (DetectionResult::Sound, 5)])).is_empty());
+  let utterance = recorder.finish().unwrap();
+  assert_eq!((utterance.end_reason, utterance.audio), (EndReason::EndOfStream, chunks(0..=5)));
+  assert!(recorder.finish().is_none());
+}
+
+#[test]
+fn the_vad_decides_what_is_speech() {
+  use DetectionResult::{Silence, Sound};
+  let mut recorder = recorder();
+  // The detector hears sound all along, the VAD only 300 ms of speech.
+  let mut ended = None;
+  for number in 0..20 {
+    let (result, vad) = match number {
+      0 => (hotword(), VadResult::Speech),
+      1..=3 => (Sound, VadResult::Speech),
+      _ => (Sound, VadResult::Silence),
+    };
+    if let Some(utterance) = recorder.push_with_vad(&chunk(number), &result, vad).unwrap() {
+      ended = Some((number, utterance.end_reason));
+      break;
+    }
+  }
+  assert_eq!(ended, Some((8, EndReason::Silence)));
+
+  // And speech keeps an utterance going through the silence of the detector.
+  let mut ended = None;
+  for number in 0..40 {
+    let result = if number == 0 { hotword() } else { Silence };
+    if let Some(utterance) = recorder.push_with_vad(&chunk(number), &result, VadResult::Speech).unwrap() {
+      ended = Some((number, utterance.end_reason));
+      break;
+    }
+  }
+  assert_eq!(ended, Some((30, EndReason::MaxLength)));
+}
+
+#[test]
+fn short_pre_rolls_keep_the_chunk_with_the_hotword() {
+  use DetectionResult::Silence;
+  for &millis in &[0, 50, 100] {
+    let mut recorder = recorder().pre_roll(millis).unwrap();
+    let results = script(&[(Silence, 3), (hotword(), 1), (Silence, 15)]);
+    let (_, utterance) = run(&mut recorder, &results).remove(0);
+    assert_eq!((utterance.start, utterance.end), (3 * 1600, 14 * 1600), "{} ms", millis);
+    assert_eq!(utterance.audio, chunks(3..=13), "{} ms", millis);
+  }
+
+  // Longer ones are kept up to the start of the stream.
+  let mut recorder = recorder().pre_roll(1000).unwrap();
+  let (_, utterance) = run(&mut recorder, &script(&[(Silence, 3), (hotword(), 1), (Silence, 15)])).remove(0);
+  assert_eq!(utterance.start, 0);
+  assert_eq!(utterance.audio, chunks(0..=13));
+
+  // A pre-roll of less than a frame is an error.
+  let result = UtteranceRecorder::<i16>::new(100, 1).unwrap().pre_roll(5);
+  assert!(matches!(result, Err(SnowboyError::InvalidArgument(_))));
+}
+
+#[test]
+fn partial_frames_are_errors() {
+  let mut recorder = UtteranceRecorder::<i16>::new(16000, 2).unwrap();
+  let result = recorder.push(&[0; 3], &hotword());
+  assert!(matches!(result, Err(SnowboyError::PartialFrame { samples: 3, channels: 2 })));
+  assert!(!recorder.is_recording());
+  assert!(matches!(UtteranceRecorder::<i16>::new(0, 1), Err(SnowboyError::InvalidArgument(_))));
+}
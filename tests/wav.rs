@@ -0,0 +1,289 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+use rsnowboy::wav::{self, WavReader, WavWriter};
+use rsnowboy::{AudioFormat, SampleFormat, SnowboyDetect, SnowboyError, SnowboyVad};
+
+const RESOURCE: &str = "resources/common.res";
+const MODEL: &str = "resources/models/snowboy.umdl";
+
+fn chunk(id: &[u8], body: &[u8]) -> Vec<u8> {
+  let mut chunk = id.to_vec();
+  chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+  chunk.extend_from_slice(body);
+  if body.len() % 2 == 1 {
+    chunk.push(0);
+  }
+  chunk
+}
+
+fn fmt_body(format_tag: u16, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Vec<u8> {
+  let block_align = channels * bits_per_sample / 8;
+  let mut body = Vec::new();
+  body.extend_from_slice(&format_tag.to_le_bytes());
+  body.extend_from_slice(&channels.to_le_bytes());
+  body.extend_from_slice(&sample_rate.to_le_bytes());
+  body.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+  body.extend_from_slice(&block_align.to_le_bytes());
+  body.extend_from_slice(&bits_per_sample.to_le_bytes());
+  body
+}
+
+fn extensible_fmt_body(format_tag: u16, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Vec<u8> {
+  let mut body = fmt_body(0xfffe, channels, sample_rate, bits_per_sample);
+  body.extend_from_slice(&22u16.to_le_bytes());
+  body.extend_from_slice(&bits_per_sample.to_le_bytes());
+  body.extend_from_slice(&0u32.to_le_bytes());
+  body.extend_from_slice(&format_tag.to_le_bytes());
+  body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71]);
+  body
+}
+
+fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+  let body = chunks.concat();
+  let mut file = b"RIFF".to_vec();
+  file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+  file.extend_from_slice(b"WAVE");
+  file.extend_from_slice(&body);
+  file
+}
+
+fn temp_path(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("rsnowboy-test-{}-{}", std::process::id(), name))
+}
+
+fn temp_wav(name: &str, bytes: &[u8]) -> PathBuf {
+  let path = temp_path(&format!("{}.wav", name));
+  fs::write(&path, bytes).unwrap();
+  path
+}
+
+#[test]
+fn bundled_wavs_have_the_detector_format() {
+  let detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  assert_eq!(detector.format(), AudioFormat::new(16000, 1, 16, SampleFormat::Int));
+  for path in &["resources/ding.wav", "resources/dong.wav"] {
+    let reader = WavReader::open(path).unwrap();
+    reader.check_format(detector.format()).unwrap();
+  }
+}
+
+#[test]
+fn reads_the_samples_after_the_header() {
+  let file = fs::read("resources/ding.wav").unwrap();
+  let mut reader = WavReader::open("resources/ding.wav").unwrap();
+  assert_eq!(reader.num_frames(), Some(7869));
+  let mut data = Vec::new();
+  reader.read_to_end(&mut data).unwrap();
+  assert_eq!(data, &file[44..]);
+}
+
+#[test]
+fn detect_file_returns_every_chunk_with_its_offset() {
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let detections = detector.detect_file("resources/ding.wav").unwrap();
+  let offsets = detections.iter().map(|detection| detection.sample_offset).collect::<Vec<_>>();
+  assert_eq!(offsets, vec![0, 1600, 3200, 4800, 6400]);
+  assert!(detections.iter().all(|detection| detection.result.hotword().is_none()));
+
+  let mut vad = SnowboyVad::new(RESOURCE).unwrap();
+  assert_eq!(vad.detect_file("resources/dong.wav").unwrap().len(), 5);
+}
+
+#[test]
+fn detect_file_rejects_another_format() {
+  let samples = vec![0u8; 1600 * 2];
+  let path = temp_wav("8k", &riff(&[chunk(b"fmt ", &fmt_body(1, 1, 8000, 16)), chunk(b"data", &samples)]));
+  let mut detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let result = detector.detect_file(&path);
+  fs::remove_file(&path).unwrap();
+  match result {
+    Err(SnowboyError::FormatMismatch { expected, found }) => {
+      assert_eq!(expected.sample_rate, 16000);
+      assert_eq!(found.sample_rate, 8000);
+    }
+    other => panic!("expected a format mismatch, got {:?}", other),
+  }
+}
+
+#[test]
+fn reads_extensible_24_bits_after_odd_chunks() {
+  let samples = [0x00, 0x00, 0x40, 0x00, 0x00, 0xc0, 0xff, 0xff, 0x7f];
+  let file = riff(&[
+    chunk(b"LIST", b"odd"),
+    chunk(b"fmt ", &extensible_fmt_body(1, 1, 16000, 24)),
+    chunk(b"fact", &[0; 4]),
+    chunk(b"data", &samples),
+  ]);
+  let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+  assert_eq!(reader.format(), AudioFormat::new(16000, 1, 24, SampleFormat::Int));
+  let mut decoded = [0.0; 4];
+  assert_eq!(reader.read_f32(&mut decoded).unwrap(), 3);
+  assert_eq!(&decoded[..3], &[0.5, -0.5, 8_388_607.0 / 8_388_608.0]);
+}
+
+#[test]
+fn reads_float_samples() {
+  let samples = [0.25f32, -1.0].iter().flat_map(|sample| sample.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+  let file = riff(&[chunk(b"fmt ", &fmt_body(3, 2, 44100, 32)), chunk(b"data", &samples)]);
+  let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+  assert_eq!(reader.format(), AudioFormat::new(44100, 2, 32, SampleFormat::Float));
+  assert_eq!(reader.num_frames(), Some(1));
+  let mut decoded = [0.0; 2];
+  reader.read_f32(&mut decoded).unwrap();
+  assert_eq!(decoded, [0.25, -1.0]);
+}
+
+#[test]
+fn truncated_samples_are_errors() {
+  // Two 24-bits samples, the second cut after its first byte.
+  let file = riff(&[chunk(b"fmt ", &fmt_body(1, 1, 16000, 24)), chunk(b"data", &[0x00, 0x00, 0x40, 0x00])]);
+  let mut reader = WavReader::new(Cursor::new(file.clone())).unwrap();
+  let mut decoded = [0.0; 4];
+  assert!(matches!(reader.read_f32(&mut decoded), Err(SnowboyError::PartialFrame { samples: 4, channels: 3 })));
+
+  // Reads that stop before it still return whole samples.
+  let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+  assert_eq!(reader.read_f32(&mut decoded[..1]).unwrap(), 1);
+  assert_eq!(decoded[0], 0.5);
+  assert!(matches!(reader.read_f32(&mut decoded), Err(SnowboyError::PartialFrame { samples: 1, channels: 3 })));
+}
+
+#[test]
+fn rejects_malformed_files() {
+  let invalid = |bytes: Vec<u8>| match WavReader::new(Cursor::new(bytes)) {
+    Err(SnowboyError::InvalidWav(reason)) => reason,
+    other => panic!("expected an invalid WAV error, got {:?}", other),
+  };
+  assert_eq!(invalid(b"RIFX\0\0\0\0WAVE".to_vec()), "not a RIFF/WAVE file");
+  assert_eq!(invalid(riff(&[chunk(b"data", &[0; 2])])), "data chunk before fmt chunk");
+  assert_eq!(invalid(riff(&[chunk(b"fmt ", &fmt_body(1, 1, 16000, 16))])), "the header is truncated");
+  assert_eq!(invalid(riff(&[chunk(b"fmt ", &fmt_body(1, 1, 16000, 12))])),
+             "12-bits PCM samples are not supported");
+  assert_eq!(invalid(riff(&[chunk(b"fmt ", &fmt_body(2, 1, 16000, 16))])), "unsupported format tag 0x0002");
+}
+
+#[test]
+fn written_files_read_back() {
+  let dir = temp_path("written");
+  fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("utterance.wav");
+  let detector = SnowboyDetect::new(RESOURCE, &[MODEL]).unwrap();
+  let samples = (0..3200).map(|i| (i % 100) as i16 * 100).collect::<Vec<i16>>();
+
+  let mut writer = WavWriter::create(&path, detector.format()).unwrap();
+  writer.write_samples(&samples[..1000]).unwrap();
+  writer.write_samples(&samples[1000..]).unwrap();
+  assert_eq!(writer.num_frames(), 3200);
+  assert!(!path.exists());
+  writer.finalize().unwrap();
+
+  let mut reader = WavReader::open(&path).unwrap();
+  assert_eq!(reader.format(), detector.format());
+  assert_eq!(reader.num_frames(), Some(3200));
+  let mut data = Vec::new();
+  reader.read_to_end(&mut data).unwrap();
+  let read = data.chunks(2).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])).collect::<Vec<i16>>();
+  assert_eq!(read, samples);
+  assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn odd_sized_data_is_padded() {
+  let path = temp_path("odd.wav");
+  let mut writer = WavWriter::create(&path, AudioFormat::new(8000, 1, 8, SampleFormat::Int)).unwrap();
+  writer.write_samples(&[0u8, 128, 255]).unwrap();
+  writer.finalize().unwrap();
+  let file = fs::read(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+  assert_eq!(file.len() % 2, 0);
+  let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+  let mut decoded = [0.0; 4];
+  assert_eq!(reader.read_f32(&mut decoded).unwrap(), 3);
+  assert_eq!(&decoded[..3], &[-1.0, 0.0, 127.0 / 128.0]);
+}
+
+#[test]
+fn writer_checks_the_sample_type() {
+  let path = temp_path("float.wav");
+  let mut writer = WavWriter::create(&path, AudioFormat::new(16000, 2, 32, SampleFormat::Float)).unwrap();
+  assert!(matches!(writer.write_samples(&[0i16, 0]), Err(SnowboyError::FormatMismatch { .. })));
+  assert!(matches!(writer.write_samples(&[0.5f32]), Err(SnowboyError::PartialFrame { samples: 1, channels: 2 })));
+  writer.write_samples(&[0.5f32, -0.5]).unwrap();
+  writer.finalize().unwrap();
+  let mut reader = WavReader::open(&path).unwrap();
+  fs::remove_file(&path).unwrap();
+  let mut decoded = [0.0; 2];
+  reader.read_f32(&mut decoded).unwrap();
+  assert_eq!(decoded, [0.5, -0.5]);
+}
+
+#[test]
+fn unfinished_files_are_removed() {
+  let dir = temp_path("unfinished");
+  fs::create_dir_all(&dir).unwrap();
+  let mut writer = WavWriter::create(dir.join("clip.wav"), AudioFormat::new(16000, 1, 16, SampleFormat::Int)).unwrap();
+  writer.write_samples(&[1i16, 2, 3]).unwrap();
+  assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+  drop(writer);
+  assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reads_rf64_sizes_from_the_ds64_chunk() {
+  let mut ds64 = Vec::new();
+  ds64.extend_from_slice(&0u64.to_le_bytes());
+  ds64.extend_from_slice(&4u64.to_le_bytes());
+  ds64.extend_from_slice(&2u64.to_le_bytes());
+  ds64.extend_from_slice(&0u32.to_le_bytes());
+  let mut file = riff(&[chunk(b"ds64", &ds64), chunk(b"fmt ", &fmt_body(1, 1, 16000, 16))]);
+  file[0..4].copy_from_slice(b"RF64");
+  file.extend_from_slice(b"data");
+  file.extend_from_slice(&u32::MAX.to_le_bytes());
+  file.extend_from_slice(&[1, 0, 2, 0, 3, 0]);
+
+  let mut reader = WavReader::new(Cursor::new(file)).unwrap();
+  assert_eq!(reader.num_frames(), Some(2));
+  let mut data = Vec::new();
+  reader.read_to_end(&mut data).unwrap();
+  assert_eq!(data, vec![1, 0, 2, 0]);
+}
+
+#[test]
+fn headers_switch_to_rf64_above_4_gib() {
+  let format = AudioFormat::new(16000, 1, 16, SampleFormat::Int);
+  // The RIFF size counts 72 bytes of header on top of the samples.
+  let largest = u64::from(u32::MAX) - 73;
+  let header = wav::header(format, largest).unwrap();
+  assert_eq!(&header[0..4], b"RIFF");
+  assert_eq!(header[4..8], (u32::MAX - 1).to_le_bytes());
+  assert_eq!(&header[12..16], b"JUNK");
+  assert_eq!(WavReader::new(&header[..]).unwrap().num_frames(), Some(largest / 2));
+
+  for &data_len in &[largest + 2, 5 << 30, 1 << 40] {
+    let header = wav::header(format, data_len).unwrap();
+    assert_eq!(header.len(), wav::header(format, 0).unwrap().len());
+    assert_eq!(&header[0..4], b"RF64");
+    assert_eq!(header[4..8], u32::MAX.to_le_bytes());
+    assert_eq!(&header[12..16], b"ds64");
+    assert_eq!(header[20..28], (data_len + 72).to_le_bytes());
+    assert_eq!(header[header.len() - 4..], u32::MAX.to_le_bytes());
+    let reader = WavReader::new(&header[..]).unwrap();
+    assert_eq!(reader.format(), format);
+    assert_eq!(reader.num_frames(), Some(data_len / 2));
+  }
+}
+
+#[test]
+fn formats_must_fit_the_header() {
+  // 400 MHz of 8 channels of 64 bits is 25.6 GB per second.
+  let format = AudioFormat::new(400_000_000, 8, 64, SampleFormat::Float);
+  assert!(matches!(wav::header(format, 0), Err(SnowboyError::InvalidWav(_))));
+  assert!(matches!(WavWriter::create(temp_path("fast.wav"), format), Err(SnowboyError::InvalidWav(_))));
+
+  let format = AudioFormat::new(16000, u16::MAX, 16, SampleFormat::Int);
+  assert!(matches!(wav::header(format, 0), Err(SnowboyError::InvalidWav(_))));
+}